@@ -1,4 +1,4 @@
-use graph::Graph;
+use graph::{Graph, GraphError};
 use std::collections::HashMap;
 
 /*******************************************************************/
@@ -17,7 +17,7 @@ struct Node<TNode, TIndex> {
 impl<TNode, TIndex: PartialEq> Node<TNode, TIndex> {
     fn new(data: TNode) -> Node<TNode, TIndex> {
         Node {
-            data: data,
+            data,
             pred: Vec::new(),
             succ: Vec::new(),
         }
@@ -58,9 +58,9 @@ struct Edge<TEdge, TIndex> {
 impl<TEdge, TIndex> Edge<TEdge, TIndex> {
     fn new(data: TEdge, start: TIndex, end: TIndex) -> Edge<TEdge, TIndex> {
         Edge {
-            data: data,
-            start: start,
-            end: end,
+            data,
+            start,
+            end,
         }
     }
 }
@@ -73,149 +73,109 @@ pub struct AdjListGraph<TNode, TEdge> {
     cur_node_idx: i64,
     cur_edge_idx: i64,
 
+    // Whether more than one edge may run from one node to another
+    parallel_edges: bool,
+
     nodes: HashMap<i64, Node<TNode, i64>>,
     edges: HashMap<i64, Edge<TEdge, i64>>,
 }
 
+impl<TNode, TEdge> Default for AdjListGraph<TNode, TEdge> {
+    fn default() -> AdjListGraph<TNode, TEdge> {
+        AdjListGraph::new()
+    }
+}
+
 impl<TNode, TEdge> AdjListGraph<TNode, TEdge> {
     pub fn new() -> AdjListGraph<TNode, TEdge> {
+        AdjListGraph::with_parallel_edges(true)
+    }
+
+    // Creates a graph that either allows or rejects (with
+    // GraphError::DuplicateEdge) a second edge between the same two nodes.
+    pub fn with_parallel_edges(allow: bool) -> AdjListGraph<TNode, TEdge> {
         AdjListGraph {
             cur_node_idx: 0i64,
             cur_edge_idx: 0i64,
 
+            parallel_edges: allow,
+
             nodes: HashMap::new(),
             edges: HashMap::new(),
         }
     }
-
-    // Returns the indegree (i.e. number of incoming edges) of a node.
-    fn get_indegree_of(&self, node: i64) -> Option<usize> {
-        if let Some(n) = self.nodes.get(&node) {
-            Some(n.pred.len())
-        } else {
-            None
-        }
-    }
-
-    // Returns the outdegree (i.e. number of outgoing edges) of a node.
-    fn get_outdegree_of(&self, node: i64) -> Option<usize> {
-        if let Some(n) = self.nodes.get(&node) {
-            Some(n.succ.len())
-        } else {
-            None
-        }
-    }
-
-    // Returns the start node of an edge.
-    fn get_edge_start(&self, edge: i64) -> Option<&i64> {
-        if let Some(e) = self.edges.get(&edge) {
-            Some(&e.start)
-        } else {
-            None
-        }
-    }
-
-    // Returns the end node of an edge.
-    fn get_edge_end(&self, edge: i64) -> Option<&i64> {
-        if let Some(e) = self.edges.get(&edge) {
-            Some(&e.end)
-        } else {
-            None
-        }
-    }
-
-    // Returns an iterator over all incoming edges of a node.
-    fn get_pred_edges_of<'a>(&'a self, node: i64) ->
-                            Box<Iterator<Item = &'a i64> + 'a> {
-        if let Some(n) = self.nodes.get(&node) {
-            Box::new(n.pred.iter())
-        } else {
-            Box::new(::std::iter::empty())
-        }
-    }
-
-    // Returns an iterator over all outgoing edges of a node.
-    fn get_succ_edges_of<'a>(&'a self, node: i64) ->
-                            Box<Iterator<Item = &'a i64> + 'a> {
-        if let Some(n) = self.nodes.get(&node) {
-            Box::new(n.succ.iter())
-        } else {
-            Box::new(::std::iter::empty())
-        }
-    }
 }
 
 impl<TNode, TEdge> Graph<TNode, TEdge> for AdjListGraph<TNode, TEdge> {
     type TIndex = i64;
 
-    fn add_node(&mut self, data: TNode) -> Self::TIndex {
+    fn try_add_node(&mut self, data: TNode) -> Result<Self::TIndex, GraphError> {
         let node_idx = self.cur_node_idx;
-        self.cur_node_idx += 1;
+        self.cur_node_idx = node_idx.checked_add(1).ok_or(GraphError::CapacityExceeded)?;
         self.nodes.insert(node_idx, Node::new(data));
-        node_idx
+        Ok(node_idx)
     }
 
     // Removes node and all incident edges
-    fn remove_node(&mut self, node: Self::TIndex) -> Option<TNode> {
-        if let Some(n) = self.nodes.remove(&node) {
-            for edge_idx in n.succ {
-                if let Some(e) = self.edges.remove(&edge_idx) {
-                    self.nodes.get_mut(&e.end).unwrap().remove_pred(edge_idx);
+    fn try_remove_node(&mut self, node: Self::TIndex) -> Result<TNode, GraphError> {
+        let n = self.nodes.remove(&node).ok_or(GraphError::MissingNode)?;
+        // The other endpoint is missing for self-loops, since the node itself
+        // has already been taken out of the map.
+        for edge_idx in n.succ {
+            if let Some(e) = self.edges.remove(&edge_idx) {
+                if let Some(end) = self.nodes.get_mut(&e.end) {
+                    end.remove_pred(edge_idx);
                 }
             }
-            for edge_idx in n.pred {
-                if let Some(e) = self.edges.remove(&edge_idx) {
-                    self.nodes.get_mut(&e.start).unwrap().remove_succ(edge_idx);
+        }
+        for edge_idx in n.pred {
+            if let Some(e) = self.edges.remove(&edge_idx) {
+                if let Some(start) = self.nodes.get_mut(&e.start) {
+                    start.remove_succ(edge_idx);
                 }
             }
-            Some(n.data)
-        } else {
-            None
         }
+        Ok(n.data)
     }
 
-    fn add_edge(&mut self, start_node: Self::TIndex, end_node: Self::TIndex, data: TEdge) -> Option<Self::TIndex> {
+    fn try_add_edge(&mut self, start_node: Self::TIndex, end_node: Self::TIndex, data: TEdge) -> Result<Self::TIndex, GraphError> {
         if !self.nodes.contains_key(&start_node) || !self.nodes.contains_key(&end_node) {
-            return None;
+            return Err(GraphError::MissingNode);
+        }
+        if !self.parallel_edges && self.has_edge_between(start_node, end_node) {
+            return Err(GraphError::DuplicateEdge);
         }
         let edge_idx = self.cur_edge_idx;
-        self.cur_edge_idx += 1;
+        self.cur_edge_idx = edge_idx.checked_add(1).ok_or(GraphError::CapacityExceeded)?;
 
         self.edges.insert(edge_idx, Edge::new(data, start_node, end_node));
-        self.nodes.get_mut(&start_node).unwrap().succ.push(edge_idx);
-        self.nodes.get_mut(&end_node).unwrap().pred.push(edge_idx);
+        if let Some(start) = self.nodes.get_mut(&start_node) {
+            start.succ.push(edge_idx);
+        }
+        if let Some(end) = self.nodes.get_mut(&end_node) {
+            end.pred.push(edge_idx);
+        }
 
-        Some(edge_idx)
+        Ok(edge_idx)
     }
 
-    fn remove_edge(&mut self, edge: Self::TIndex) -> Option<TEdge> {
-        if let Some(e) = self.edges.remove(&edge) {
-            self.nodes.get_mut(&e.start).unwrap().remove_succ(edge);
-            self.nodes.get_mut(&e.end).unwrap().remove_pred(edge);
-            Some(e.data)
-        } else {
-            None
+    fn try_remove_edge(&mut self, edge: Self::TIndex) -> Result<TEdge, GraphError> {
+        let e = self.edges.remove(&edge).ok_or(GraphError::MissingEdge)?;
+        if let Some(start) = self.nodes.get_mut(&e.start) {
+            start.remove_succ(edge);
         }
-    }
-
-    fn remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<TEdge> {
-        let mut edge_opt: Option<Self::TIndex> = None;
-        if let Some(start) = self.nodes.get(&node_a) {
-            for edge_idx in start.succ.iter() {
-                let edge = self.edges.get(edge_idx).unwrap();
-                if edge.end == node_b {
-                    edge_opt = Some(*edge_idx);
-                }
-            }
+        if let Some(end) = self.nodes.get_mut(&e.end) {
+            end.remove_pred(edge);
         }
+        Ok(e.data)
+    }
 
-        if let Some(e) = edge_opt {
-            self.nodes.get_mut(&node_a).unwrap().remove_succ(e);
-            self.nodes.get_mut(&node_b).unwrap().remove_pred(e);
-            Some(self.edges.remove(&e).unwrap().data)
-        } else {
-            None
+    fn try_remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Result<TEdge, GraphError> {
+        if !self.nodes.contains_key(&node_a) || !self.nodes.contains_key(&node_b) {
+            return Err(GraphError::MissingNode);
         }
+        let edge = self.get_edge(node_a, node_b).ok_or(GraphError::MissingEdge)?;
+        self.try_remove_edge(edge)
     }
 
     fn clear(&mut self) {
@@ -250,7 +210,7 @@ impl<TNode, TEdge> Graph<TNode, TEdge> for AdjListGraph<TNode, TEdge> {
     fn has_edge_between(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> bool {
         if let Some(start) = self.nodes.get(&node_a) {
             for edge_idx in start.succ.iter() {
-                let edge = self.edges.get(&edge_idx).unwrap();
+                let edge = self.edges.get(edge_idx).unwrap();
                 if edge.end == node_b {
                     return true;
                 }
@@ -259,32 +219,40 @@ impl<TNode, TEdge> Graph<TNode, TEdge> for AdjListGraph<TNode, TEdge> {
         false
     }
 
-    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<&Self::TIndex> {
+    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<Self::TIndex> {
         if let Some(start) = self.nodes.get(&node_a) {
             for edge_idx in start.succ.iter() {
-                let edge = self.edges.get(&edge_idx).unwrap();
+                let edge = self.edges.get(edge_idx).unwrap();
                 if edge.end == node_b {
-                    return Some(edge_idx);
+                    return Some(*edge_idx);
                 }
             }
         }
         None
     }
 
-    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(&Self::TIndex, &Self::TIndex)> {
-        if let Some(e) = self.edges.get(&edge) {
-            Some((&e.start, &e.end))
-        } else {
-            None
-        }
+    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(Self::TIndex, Self::TIndex)> {
+        self.edges.get(&edge).map(|e| (e.start, e.end))
     }
 
     fn get_degree_of(&self, node: Self::TIndex) -> Option<usize> {
-        if let Some(n) = self.nodes.get(&node) {
-            Some(n.pred.len() + n.succ.len())
-        } else {
-            None
-        }
+        self.nodes.get(&node).map(|n| n.pred.len() + n.succ.len())
+    }
+
+    fn get_indegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.nodes.get(&node).map(|n| n.pred.len())
+    }
+
+    fn get_outdegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.nodes.get(&node).map(|n| n.succ.len())
+    }
+
+    fn get_edge_start(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edges.get(&edge).map(|e| e.start)
+    }
+
+    fn get_edge_end(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edges.get(&edge).map(|e| e.end)
     }
 
     fn get_edge_data(&self, edge: Self::TIndex) -> Option<&TEdge> {
@@ -295,20 +263,102 @@ impl<TNode, TEdge> Graph<TNode, TEdge> for AdjListGraph<TNode, TEdge> {
         }
     }
 
-    fn nodes<'a>(&'a self) -> Box<Iterator<Item = &'a Self::TIndex> + 'a> {
-        Box::new(self.nodes.keys())
+    fn get_node_data_mut(&mut self, node: Self::TIndex) -> Option<&mut TNode> {
+        self.nodes.get_mut(&node).map(|n| &mut n.data)
+    }
+
+    fn get_edge_data_mut(&mut self, edge: Self::TIndex) -> Option<&mut TEdge> {
+        self.edges.get_mut(&edge).map(|e| &mut e.data)
     }
 
-    fn edges<'a>(&'a self) -> Box<Iterator<Item = &'a Self::TIndex> + 'a> {
-        Box::new(self.edges.keys())
+    fn nodes<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.nodes.keys().cloned())
+    }
+
+    fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.edges.keys().cloned())
+    }
+
+    fn nodes_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TNode)> + 'a> {
+        Box::new(self.nodes.iter_mut().map(|(&idx, n)| (idx, &mut n.data)))
+    }
+
+    fn edges_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TEdge)> + 'a> {
+        Box::new(self.edges.iter_mut().map(|(&idx, e)| (idx, &mut e.data)))
     }
 
     fn get_edges_of<'a>(&'a self, node: Self::TIndex) ->
-                            Box<Iterator<Item = &'a Self::TIndex> + 'a> {
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            Box::new(n.pred.iter().chain(n.succ.iter()).cloned())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
         if let Some(n) = self.nodes.get(&node) {
-            Box::new(n.pred.iter().chain(n.succ.iter()))
+            Box::new(n.pred.iter().cloned())
         } else {
             Box::new(::std::iter::empty())
         }
     }
+
+    fn get_succ_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            Box::new(n.succ.iter().cloned())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        let edges = &self.edges;
+        Box::new(self.get_pred_edges_of(node).map(move |e| edges[&e].start))
+    }
+
+    fn get_succ_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        let edges = &self.edges;
+        Box::new(self.get_succ_edges_of(node).map(move |e| edges[&e].end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_mutators_report_failure_reason() {
+        let mut g = AdjListGraph::with_parallel_edges(false);
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let ab = g.try_add_edge(a, b, 1).unwrap();
+
+        assert_eq!(g.try_add_edge(a, b, 2), Err(GraphError::DuplicateEdge));
+        assert_eq!(g.try_add_edge(a, 42, 2), Err(GraphError::MissingNode));
+        assert_eq!(g.try_remove_edge_between(b, a), Err(GraphError::MissingEdge));
+        assert_eq!(g.try_remove_edge_between(42, a), Err(GraphError::MissingNode));
+        assert_eq!(g.try_remove_edge(ab), Ok(1));
+        assert_eq!(g.try_remove_edge(ab), Err(GraphError::MissingEdge));
+        assert_eq!(g.try_remove_node(a), Ok("a"));
+        assert_eq!(g.try_remove_node(a), Err(GraphError::MissingNode));
+    }
+
+    #[test]
+    fn remove_node_with_self_loop() {
+        let mut g = AdjListGraph::new();
+        let a = g.add_node(0);
+        let b = g.add_node(1);
+        g.add_edge(a, a, ());
+        g.add_edge(a, b, ());
+        g.add_edge(a, b, ());
+
+        assert_eq!(g.remove_node(a), Some(0));
+        assert_eq!(g.size(), 0);
+        assert_eq!(g.get_degree_of(b), Some(0));
+    }
 }
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::cmp;
+
+// Number of values that fit in the first chunk.
+const INITIAL_CHUNK_CAPACITY: usize = 64;
+
+struct ChunkList<T> {
+    // The chunk currently being filled
+    current: Vec<T>,
+
+    // Chunks that are already full
+    rest: Vec<Vec<T>>,
+
+    // Total number of values across all chunks
+    len: usize,
+}
+
+impl<T> ChunkList<T> {
+    // Retires the current chunk and starts a new one that is big enough to
+    // hold at least `additional` more values.
+    fn grow(&mut self, additional: usize) {
+        let capacity = cmp::max(self.current.capacity() * 2, additional);
+        let chunk = ::std::mem::replace(&mut self.current, Vec::with_capacity(capacity));
+        if !chunk.is_empty() {
+            self.rest.push(chunk);
+        }
+    }
+
+    // Finds the chunk holding the idx-th allocated value. There are only
+    // logarithmically many chunks since each one doubles in size.
+    fn get(&self, mut idx: usize) -> Option<&T> {
+        for chunk in self.rest.iter().chain(::std::iter::once(&self.current)) {
+            if idx < chunk.len() {
+                return Some(&chunk[idx]);
+            }
+            idx -= chunk.len();
+        }
+        None
+    }
+
+    fn get_mut(&mut self, mut idx: usize) -> Option<&mut T> {
+        for chunk in self.rest.iter_mut().chain(::std::iter::once(&mut self.current)) {
+            if idx < chunk.len() {
+                return Some(&mut chunk[idx]);
+            }
+            idx -= chunk.len();
+        }
+        None
+    }
+}
+
+/**
+ * A typed memory arena that works on stable Rust. Values are pushed into
+ * chunks whose capacity is reserved up front, so a chunk never reallocates
+ * and every value keeps its address until the arena itself is dropped, at
+ * which point everything is freed at once. Each new chunk is twice the size
+ * of the previous one.
+ */
+pub struct TypedArena<T> {
+    chunks: RefCell<ChunkList<T>>,
+}
+
+impl<T> TypedArena<T> {
+    pub fn new() -> TypedArena<T> {
+        TypedArena::with_capacity(INITIAL_CHUNK_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> TypedArena<T> {
+        TypedArena {
+            chunks: RefCell::new(ChunkList {
+                current: Vec::with_capacity(cmp::max(capacity, 1)),
+                rest: Vec::new(),
+                len: 0,
+            }),
+        }
+    }
+
+    // Moves a value into the arena and returns a reference to it that lives
+    // as long as the arena.
+    pub fn alloc(&self, value: T) -> &T {
+        let mut chunks = self.chunks.borrow_mut();
+        if chunks.current.len() == chunks.current.capacity() {
+            chunks.grow(1);
+        }
+
+        let idx = chunks.current.len();
+        chunks.current.push(value);
+        chunks.len += 1;
+
+        // The push above never reallocates the chunk, and chunks are only
+        // ever moved as a whole (which leaves their heap buffer in place), so
+        // the value stays at this address until the arena is dropped.
+        unsafe { &*chunks.current.as_ptr().add(idx) }
+    }
+
+    // Returns the number of values allocated in the arena. Values are never
+    // removed, so this is also the index the next allocation will get.
+    pub fn len(&self) -> usize {
+        self.chunks.borrow().len
+    }
+
+    // Returns the idx-th value allocated in the arena.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        let chunks = self.chunks.borrow();
+        // Values never move once allocated (see alloc), so the reference can
+        // outlive the borrow of the chunk list.
+        chunks.get(idx).map(|value| unsafe { &*(value as *const T) })
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        self.chunks.get_mut().get_mut(idx)
+    }
+
+    // Iterates over all values in allocation order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        let chunks = self.chunks.get_mut();
+        chunks.rest.iter_mut()
+            .chain(::std::iter::once(&mut chunks.current))
+            .flat_map(|chunk| chunk.iter_mut())
+    }
+}
+
+impl<T> Default for TypedArena<T> {
+    fn default() -> TypedArena<T> {
+        TypedArena::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_keep_their_address() {
+        let arena = TypedArena::with_capacity(2);
+        let first = arena.alloc(0usize);
+        let refs: Vec<&usize> = (1..100).map(|i| arena.alloc(i)).collect();
+
+        assert_eq!(*first, 0);
+        for (i, r) in refs.iter().enumerate() {
+            assert_eq!(**r, i + 1);
+        }
+    }
+
+    #[test]
+    fn index_across_chunks() {
+        let mut arena = TypedArena::with_capacity(3);
+        for i in 0..50 {
+            arena.alloc(i);
+        }
+
+        assert_eq!(arena.len(), 50);
+        assert_eq!(arena.get(0), Some(&0));
+        assert_eq!(arena.get(2), Some(&2));
+        assert_eq!(arena.get(3), Some(&3));
+        assert_eq!(arena.get(49), Some(&49));
+        assert_eq!(arena.get(50), None);
+
+        *arena.get_mut(20).unwrap() = 200;
+        assert_eq!(arena.get(20), Some(&200));
+    }
+}
@@ -1,40 +1,30 @@
-use std::cell::UnsafeCell;
 use arena::TypedArena;
+use graph::{Graph, GraphError};
 
 /**
- * This graph implementation utilizes a memory arena (only in unstable Rust),
- * which allocates nodes and edges in what is essentially a large vector.
+ * This graph implementation utilizes a memory arena (see arena.rs), which
+ * allocates nodes and edges in chunks of what is essentially a large vector.
  * Everything is deallocated all at once when the arena is dropped.
+ *
+ * Since arena memory is never handed back, removing a node or an edge leaves
+ * a tombstone in its slot: the payload is moved out and the element is
+ * unlinked from its neighbors, so it no longer shows up in iteration or
+ * degree counts, but the slot itself stays put until the graph is cleared
+ * or dropped.
  */
 
 // ============================================================================
 // EDGE IMPLEMENTATION
 // ============================================================================
 
-// 'a here indicates the lifetime of our Edge and its associated data.
 #[derive(Debug)]
-pub struct Edge<'a, TEdge: 'a, TNode: 'a> {
-    // Associated data for this edge
-    data: TEdge,
+struct Edge<TEdge> {
+    // Associated data for this edge, or None once the edge has been removed
+    data: Option<TEdge>,
 
     // Start and end nodes for this edge
-    start: &'a Node<'a, TNode, TEdge>,
-    end: &'a Node<'a, TNode, TEdge>,
-}
-
-impl<'a, TEdge, TNode> Edge<'a, TEdge, TNode> {
-    fn new<'b>(
-        arena: &'b TypedArena<Edge<'b, TEdge, TNode>>,
-        data: TEdge,
-        start: &'b Node<'b, TNode, TEdge>,
-        end: &'b Node<'b, TNode, TEdge>,
-    ) -> &'b Edge<'b, TEdge, TNode> {
-        arena.alloc(Edge {
-            data: data,
-            start: start,
-            end: end,
-        })
-    }
+    start: usize,
+    end: usize,
 }
 
 // ============================================================================
@@ -42,25 +32,18 @@ impl<'a, TEdge, TNode> Edge<'a, TEdge, TNode> {
 // ============================================================================
 
 #[derive(Debug)]
-pub struct Node<'a, TNode: 'a, TEdge: 'a> {
-    data: TNode,
+struct Node<TNode> {
+    // Associated data for this node, or None once the node has been removed
+    data: Option<TNode>,
 
     // This is a directed graph, so each node has predecessors and successors
-    predecessors: UnsafeCell<Vec<&'a Edge<'a, TEdge, TNode>>>,
-    successors: UnsafeCell<Vec<&'a Edge<'a, TEdge, TNode>>>,
+    predecessors: Vec<usize>,
+    successors: Vec<usize>,
 }
 
-
-impl<'a, TNode: 'a, TEdge: 'a> Node<'a, TNode, TEdge> {
-    fn new<'b>(
-        arena: &'b TypedArena<Node<'b, TNode, TEdge>>,
-        data: TNode,
-    ) -> &'b Node<'b, TNode, TEdge> {
-        arena.alloc(Node {
-            data: data,
-            predecessors: UnsafeCell::new(Vec::new()),
-            successors: UnsafeCell::new(Vec::new()),
-        })
+fn remove_from(edges: &mut Vec<usize>, edge: usize) {
+    if let Some(pos) = edges.iter().position(|&e| e == edge) {
+        edges.swap_remove(pos);
     }
 }
 
@@ -68,54 +51,356 @@ impl<'a, TNode: 'a, TEdge: 'a> Node<'a, TNode, TEdge> {
 // GRAPH IMPLEMENTATION
 // ============================================================================
 
-pub struct ArenaGraph<'a, TNode: 'a, TEdge: 'a> {
-    nodes: TypedArena<Node<'a, TNode, TEdge>>,
-    edges: TypedArena<Edge<'a, TEdge, TNode>>,
+pub struct ArenaGraph<TNode, TEdge> {
+    nodes: TypedArena<Node<TNode>>,
+    edges: TypedArena<Edge<TEdge>>,
+
+    // Indices handed out before the last clear() are offset by these, so
+    // that stale indices never alias elements allocated afterwards.
+    node_base: usize,
+    edge_base: usize,
+
+    // Number of elements that have not been tombstoned
+    live_nodes: usize,
+    live_edges: usize,
+
+    // Whether more than one edge may run from one node to another
+    parallel_edges: bool,
 }
 
-impl<'a, TNode, TEdge> ArenaGraph<'a, TNode, TEdge> {
-    pub fn new<'b>() -> ArenaGraph<'b, TNode, TEdge> {
+impl<TNode, TEdge> Default for ArenaGraph<TNode, TEdge> {
+    fn default() -> ArenaGraph<TNode, TEdge> {
+        ArenaGraph::new()
+    }
+}
+
+impl<TNode, TEdge> ArenaGraph<TNode, TEdge> {
+    pub fn new() -> ArenaGraph<TNode, TEdge> {
+        ArenaGraph::with_parallel_edges(true)
+    }
+
+    // Creates a graph that either allows or rejects (with
+    // GraphError::DuplicateEdge) a second edge between the same two nodes.
+    pub fn with_parallel_edges(allow: bool) -> ArenaGraph<TNode, TEdge> {
         ArenaGraph {
             nodes: TypedArena::new(),
             edges: TypedArena::new(),
+            node_base: 0,
+            edge_base: 0,
+            live_nodes: 0,
+            live_edges: 0,
+            parallel_edges: allow,
         }
     }
 
-    pub fn add_node(&'a self, data: TNode) -> &'a Node<'a, TNode, TEdge> {
-        Node::new(&self.nodes, data)
+    // Returns a node that has not been tombstoned.
+    fn node(&self, node: usize) -> Option<&Node<TNode>> {
+        node.checked_sub(self.node_base)
+            .and_then(|slot| self.nodes.get(slot))
+            .and_then(|n| if n.data.is_some() { Some(n) } else { None })
+    }
+
+    fn node_mut(&mut self, node: usize) -> Option<&mut Node<TNode>> {
+        node.checked_sub(self.node_base)
+            .and_then(move |slot| self.nodes.get_mut(slot))
+            .and_then(|n| if n.data.is_some() { Some(n) } else { None })
     }
 
-    // Removes node and all incident edges
-    fn remove_node(
-        &mut self,
-        node: &'a Node<'a, TNode, TEdge>,
-    ) -> Option<TNode> {
-        unimplemented!();
+    // Returns an edge that has not been tombstoned.
+    fn edge(&self, edge: usize) -> Option<&Edge<TEdge>> {
+        edge.checked_sub(self.edge_base)
+            .and_then(|slot| self.edges.get(slot))
+            .and_then(|e| if e.data.is_some() { Some(e) } else { None })
     }
 
-    pub fn add_edge(
-        &'a self,
-        start_node: &'a Node<'a, TNode, TEdge>,
-        end_node: &'a Node<'a, TNode, TEdge>,
-        data: TEdge
-    ) -> &'a Edge<'a, TEdge, TNode> {
-        let edge = Edge::new(
-            &self.edges,
-            data,
-            start_node,
-            end_node,
-        );
+    fn edge_mut(&mut self, edge: usize) -> Option<&mut Edge<TEdge>> {
+        edge.checked_sub(self.edge_base)
+            .and_then(move |slot| self.edges.get_mut(slot))
+            .and_then(|e| if e.data.is_some() { Some(e) } else { None })
+    }
 
-        unsafe {
-            (*start_node.successors.get()).push(edge);
-            (*end_node.predecessors.get()).push(edge);
+    // Tombstones an edge and unlinks it from both of its endpoints.
+    fn bury_edge(&mut self, edge: usize) -> Result<TEdge, GraphError> {
+        let (data, start, end) = match self.edge_mut(edge) {
+            Some(e) => (e.data.take(), e.start, e.end),
+            None => return Err(GraphError::MissingEdge),
+        };
+        if let Some(n) = self.node_mut(start) {
+            remove_from(&mut n.successors, edge);
         }
+        if let Some(n) = self.node_mut(end) {
+            remove_from(&mut n.predecessors, edge);
+        }
+        self.live_edges -= 1;
+        data.ok_or(GraphError::MissingEdge)
+    }
+}
+
+impl<TNode, TEdge> Graph<TNode, TEdge> for ArenaGraph<TNode, TEdge> {
+    type TIndex = usize;
 
-        edge
+    fn try_add_node(&mut self, data: TNode) -> Result<Self::TIndex, GraphError> {
+        let node_idx = self.node_base.checked_add(self.nodes.len())
+            .ok_or(GraphError::CapacityExceeded)?;
+        self.nodes.alloc(Node {
+            data: Some(data),
+            predecessors: Vec::new(),
+            successors: Vec::new(),
+        });
+        self.live_nodes += 1;
+        Ok(node_idx)
     }
 
-    // use drop() instead
+    // Removes node and all incident edges, leaving tombstones behind
+    fn try_remove_node(&mut self, node: Self::TIndex) -> Result<TNode, GraphError> {
+        let incident: Vec<usize> = match self.node(node) {
+            Some(n) => n.successors.iter().chain(n.predecessors.iter()).cloned().collect(),
+            None => return Err(GraphError::MissingNode),
+        };
+        // A self-loop is listed twice, so the second attempt finds a tombstone.
+        for edge in incident {
+            let _ = self.bury_edge(edge);
+        }
+
+        let data = self.node_mut(node).and_then(|n| n.data.take());
+        self.live_nodes -= 1;
+        data.ok_or(GraphError::MissingNode)
+    }
+
+    fn try_add_edge(&mut self, start_node: Self::TIndex, end_node: Self::TIndex, data: TEdge) -> Result<Self::TIndex, GraphError> {
+        if !self.has_node(start_node) || !self.has_node(end_node) {
+            return Err(GraphError::MissingNode);
+        }
+        if !self.parallel_edges && self.has_edge_between(start_node, end_node) {
+            return Err(GraphError::DuplicateEdge);
+        }
+        let edge_idx = self.edge_base.checked_add(self.edges.len())
+            .ok_or(GraphError::CapacityExceeded)?;
+        self.edges.alloc(Edge {
+            data: Some(data),
+            start: start_node,
+            end: end_node,
+        });
+        if let Some(start) = self.node_mut(start_node) {
+            start.successors.push(edge_idx);
+        }
+        if let Some(end) = self.node_mut(end_node) {
+            end.predecessors.push(edge_idx);
+        }
+        self.live_edges += 1;
+
+        Ok(edge_idx)
+    }
+
+    fn try_remove_edge(&mut self, edge: Self::TIndex) -> Result<TEdge, GraphError> {
+        self.bury_edge(edge)
+    }
+
+    fn try_remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Result<TEdge, GraphError> {
+        if !self.has_node(node_a) || !self.has_node(node_b) {
+            return Err(GraphError::MissingNode);
+        }
+        let edge = self.get_edge(node_a, node_b).ok_or(GraphError::MissingEdge)?;
+        self.bury_edge(edge)
+    }
+
+    // Drops both arenas, freeing every node and edge (tombstones included)
+    // all at once.
     fn clear(&mut self) {
-        unimplemented!()
+        self.node_base += self.nodes.len();
+        self.edge_base += self.edges.len();
+        self.nodes = TypedArena::new();
+        self.edges = TypedArena::new();
+        self.live_nodes = 0;
+        self.live_edges = 0;
+    }
+
+    fn order(&self) -> usize {
+        self.live_nodes
+    }
+
+    fn size(&self) -> usize {
+        self.live_edges
+    }
+
+    fn has_node(&self, node: Self::TIndex) -> bool {
+        self.node(node).is_some()
+    }
+
+    fn get_node_data(&self, node: Self::TIndex) -> Option<&TNode> {
+        self.node(node).and_then(|n| n.data.as_ref())
+    }
+
+    fn has_edge(&self, edge: Self::TIndex) -> bool {
+        self.edge(edge).is_some()
+    }
+
+    fn has_edge_between(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> bool {
+        self.get_edge(node_a, node_b).is_some()
+    }
+
+    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<Self::TIndex> {
+        if let Some(start) = self.node(node_a) {
+            for &edge_idx in start.successors.iter() {
+                if self.edge(edge_idx).map(|e| e.end) == Some(node_b) {
+                    return Some(edge_idx);
+                }
+            }
+        }
+        None
+    }
+
+    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(Self::TIndex, Self::TIndex)> {
+        self.edge(edge).map(|e| (e.start, e.end))
+    }
+
+    fn get_degree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.node(node).map(|n| n.predecessors.len() + n.successors.len())
+    }
+
+    fn get_indegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.node(node).map(|n| n.predecessors.len())
+    }
+
+    fn get_outdegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.node(node).map(|n| n.successors.len())
+    }
+
+    fn get_edge_start(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edge(edge).map(|e| e.start)
+    }
+
+    fn get_edge_end(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edge(edge).map(|e| e.end)
+    }
+
+    fn get_edge_data(&self, edge: Self::TIndex) -> Option<&TEdge> {
+        self.edge(edge).and_then(|e| e.data.as_ref())
+    }
+
+    fn get_node_data_mut(&mut self, node: Self::TIndex) -> Option<&mut TNode> {
+        self.node_mut(node).and_then(|n| n.data.as_mut())
+    }
+
+    fn get_edge_data_mut(&mut self, edge: Self::TIndex) -> Option<&mut TEdge> {
+        self.edge_mut(edge).and_then(|e| e.data.as_mut())
+    }
+
+    // The iterators below skip over tombstones.
+
+    fn nodes<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        let base = self.node_base;
+        Box::new((base..base + self.nodes.len()).filter(move |&n| self.has_node(n)))
+    }
+
+    fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        let base = self.edge_base;
+        Box::new((base..base + self.edges.len()).filter(move |&e| self.has_edge(e)))
+    }
+
+    fn nodes_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TNode)> + 'a> {
+        let base = self.node_base;
+        Box::new(self.nodes.iter_mut().enumerate()
+            .filter_map(move |(slot, n)| n.data.as_mut().map(|data| (base + slot, data))))
+    }
+
+    fn edges_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TEdge)> + 'a> {
+        let base = self.edge_base;
+        Box::new(self.edges.iter_mut().enumerate()
+            .filter_map(move |(slot, e)| e.data.as_mut().map(|data| (base + slot, data))))
+    }
+
+    fn get_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.node(node) {
+            Box::new(n.predecessors.iter().chain(n.successors.iter()).cloned())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.node(node) {
+            Box::new(n.predecessors.iter().cloned())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_succ_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.node(node) {
+            Box::new(n.successors.iter().cloned())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.get_pred_edges_of(node).filter_map(move |e| self.get_edge_start(e)))
+    }
+
+    fn get_succ_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.get_succ_edges_of(node).filter_map(move |e| self.get_edge_end(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_elements_disappear() {
+        let mut g = ArenaGraph::new();
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let c = g.add_node("c");
+        let ab = g.add_edge(a, b, 1).unwrap();
+        g.add_edge(b, c, 2);
+        g.add_edge(c, b, 3);
+        g.add_edge(b, b, 4);
+
+        assert_eq!(g.remove_node(b), Some("b"));
+        assert_eq!(g.remove_node(b), None);
+        assert_eq!((g.order(), g.size()), (2, 0));
+        assert_eq!(g.nodes().collect::<Vec<_>>(), vec![a, c]);
+        assert_eq!(g.edges().count(), 0);
+        assert_eq!(g.get_degree_of(a), Some(0));
+        assert_eq!(g.get_degree_of(c), Some(0));
+        assert!(!g.has_edge(ab));
+        assert_eq!(g.add_edge(a, b, 5), None);
+    }
+
+    #[test]
+    fn remove_edge_leaves_endpoints() {
+        let mut g = ArenaGraph::new();
+        let a = g.add_node(0);
+        let b = g.add_node(1);
+        let ab = g.add_edge(a, b, 'x').unwrap();
+        let ba = g.add_edge(b, a, 'y').unwrap();
+
+        assert_eq!(g.remove_edge(ab), Some('x'));
+        assert_eq!(g.remove_edge(ab), None);
+        assert_eq!(g.edges().collect::<Vec<_>>(), vec![ba]);
+        assert_eq!(g.get_succ_nodes_of(b).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(g.get_outdegree_of(a), Some(0));
+        assert_eq!(g.remove_edge_between(b, a), Some('y'));
+        assert_eq!(g.order(), 2);
+    }
+
+    #[test]
+    fn clear_invalidates_old_indices() {
+        let mut g = ArenaGraph::new();
+        let a = g.add_node(0);
+        g.add_edge(a, a, 0);
+        g.clear();
+
+        assert_eq!((g.order(), g.size()), (0, 0));
+        let b = g.add_node(1);
+        assert!(!g.has_node(a));
+        assert_eq!(g.get_node_data(b), Some(&1));
     }
 }
@@ -0,0 +1,224 @@
+use graph::Graph;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use traversal::{incident_edges, Direction};
+
+// Everything here works on the undirected view of the graph. Self-loops never
+// affect connectivity and are left out; two parallel edges between the same
+// nodes form a cycle, so neither is a bridge.
+
+struct Decomposition<T> {
+    articulation_points: Vec<T>,
+    bridges: Vec<T>,
+    components: Vec<HashSet<T>>,
+}
+
+// A node on the explicit DFS stack: the node, the tree edge it was reached
+// by, and how far through its incident edges the search has got.
+struct Frame<T> {
+    node: usize,
+    via: Option<T>,
+    next: usize,
+}
+
+// Hopcroft and Tarjan's depth-first search, run with an explicit stack so it
+// handles paths of any length. low[u] is the earliest discovery time
+// reachable from u's subtree through at most one back edge; a child subtree
+// that cannot climb above its parent cuts the graph at the parent. Edges are
+// stacked as they are explored and popped off as one component whenever
+// such a cut closes.
+fn decompose<N, E, G>(graph: &G) -> Decomposition<G::TIndex>
+    where G: Graph<N, E>
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let slot: HashMap<G::TIndex, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let adj: Vec<Vec<(G::TIndex, usize)>> = nodes.iter()
+        .enumerate()
+        .map(|(i, &n)| {
+            incident_edges(graph, n, Direction::Both)
+                .map(|(e, other)| (e, slot[&other]))
+                .filter(|&(_, j)| j != i)
+                .collect()
+        })
+        .collect();
+
+    let n = nodes.len();
+    let mut disc: Vec<Option<usize>> = vec![None; n];
+    let mut low = vec![0; n];
+    let mut is_cut = vec![false; n];
+    let mut time = 0;
+    let mut stack: Vec<Frame<G::TIndex>> = Vec::new();
+    let mut edges: Vec<G::TIndex> = Vec::new();
+    let mut result = Decomposition {
+        articulation_points: Vec::new(),
+        bridges: Vec::new(),
+        components: Vec::new(),
+    };
+
+    for root in 0..n {
+        if disc[root].is_some() {
+            continue;
+        }
+        disc[root] = Some(time);
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        stack.push(Frame { node: root, via: None, next: 0 });
+
+        while let Some(frame) = stack.last_mut() {
+            let u = frame.node;
+            let d = disc[u].unwrap_or(0);
+            if frame.next < adj[u].len() {
+                let (e, v) = adj[u][frame.next];
+                frame.next += 1;
+                if Some(e) == frame.via {
+                    continue;
+                }
+                match disc[v] {
+                    None => {
+                        edges.push(e);
+                        disc[v] = Some(time);
+                        low[v] = time;
+                        time += 1;
+                        if u == root {
+                            root_children += 1;
+                        }
+                        stack.push(Frame { node: v, via: Some(e), next: 0 });
+                    }
+                    Some(dv) if dv < d => {
+                        // Back edge to an ancestor
+                        edges.push(e);
+                        low[u] = low[u].min(dv);
+                    }
+                    // A back edge seen again from the ancestor's end; it was
+                    // stacked when the descendant reached it.
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            let via = frame.via;
+            stack.pop();
+            let (parent, tree_edge) = match (stack.last(), via) {
+                (Some(p), Some(e)) => (p.node, e),
+                _ => continue,
+            };
+            low[parent] = low[parent].min(low[u]);
+            let parent_disc = disc[parent].unwrap_or(0);
+            if low[u] > parent_disc {
+                result.bridges.push(tree_edge);
+            }
+            if low[u] >= parent_disc {
+                if parent != root {
+                    is_cut[parent] = true;
+                }
+                let mut component = HashSet::new();
+                while let Some(e) = edges.pop() {
+                    component.insert(e);
+                    if e == tree_edge {
+                        break;
+                    }
+                }
+                result.components.push(component);
+            }
+        }
+        if root_children > 1 {
+            is_cut[root] = true;
+        }
+    }
+
+    result.articulation_points = (0..n).filter(|&i| is_cut[i]).map(|i| nodes[i]).collect();
+    result
+}
+
+// Returns the articulation points: the nodes whose removal splits their
+// connected component.
+pub fn articulation_points<N, E, G>(graph: &G) -> Vec<G::TIndex>
+    where G: Graph<N, E>
+{
+    decompose(graph).articulation_points
+}
+
+// Returns the bridges: the edges whose removal splits their connected
+// component.
+pub fn bridges<N, E, G>(graph: &G) -> Vec<G::TIndex>
+    where G: Graph<N, E>
+{
+    decompose(graph).bridges
+}
+
+// Splits the edges into biconnected components: maximal sets of edges in
+// which any two lie on a common simple cycle. A bridge is a component of its
+// own. Components meet only at articulation points.
+pub fn biconnected_components<N, E, G>(graph: &G) -> Vec<HashSet<G::TIndex>>
+    where G: Graph<N, E>, G::TIndex: Hash
+{
+    decompose(graph).components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+    use io;
+
+    // Triangle {0, 1, 2} joined through the bridge 2 - 3 to the square
+    // {3, 4, 5, 6}, with a pendant 7 off 6, a self-loop on 5 and a parallel
+    // pair between 0 and 1. Edge directions are mixed on purpose.
+    fn bowtie() -> (AdjListGraph<(), ()>, Vec<i64>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..8).map(|_| g.add_node(())).collect();
+        let e: Vec<i64> = [(0, 1), (2, 1), (0, 2), (2, 3), (3, 4), (5, 4), (5, 6), (3, 6), (7, 6), (5, 5), (1, 0)]
+            .iter()
+            .map(|&(a, b)| g.add_edge(n[a], n[b], ()).unwrap())
+            .collect();
+        (g, n, e)
+    }
+
+    fn sorted(mut v: Vec<i64>) -> Vec<i64> {
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn cut_points_and_bridges() {
+        let (g, n, e) = bowtie();
+        assert_eq!(sorted(articulation_points(&g)), vec![n[2], n[3], n[6]]);
+        assert_eq!(sorted(bridges(&g)), vec![e[3], e[8]]);
+
+        let mut twitter = AdjListGraph::new();
+        let ids = io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        assert_eq!(articulation_points(&twitter), vec![ids[&12]]);
+        assert_eq!(bridges(&twitter).len(), twitter.size());
+    }
+
+    #[test]
+    fn components_partition_the_edges() {
+        let (g, _, e) = bowtie();
+        let mut components: Vec<Vec<i64>> = biconnected_components(&g)
+            .into_iter()
+            .map(|c| sorted(c.into_iter().collect()))
+            .collect();
+        components.sort();
+        assert_eq!(components, vec![
+            vec![e[0], e[1], e[2], e[10]],
+            vec![e[3]],
+            vec![e[4], e[5], e[6], e[7]],
+            vec![e[8]],
+        ]);
+    }
+
+    #[test]
+    fn long_path_does_not_recurse() {
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let n: Vec<i64> = (0..100_000).map(|_| g.add_node(())).collect();
+        for w in n.windows(2) {
+            g.add_edge(w[0], w[1], ());
+        }
+        assert_eq!(bridges(&g).len(), n.len() - 1);
+        assert_eq!(articulation_points(&g).len(), n.len() - 2);
+        g.add_edge(n[n.len() - 1], n[0], ());
+        assert!(bridges(&g).is_empty());
+        assert_eq!(biconnected_components(&g).len(), 1);
+    }
+}
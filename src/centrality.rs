@@ -0,0 +1,298 @@
+use graph::Graph;
+use ranking::Dense;
+use rng::Rng;
+use std::collections::{HashMap, VecDeque};
+
+// All measures here count hops and follow edges in their direction. Nodes
+// that cannot reach each other simply do not contribute to one another.
+
+// Breadth-first search from source, filling dist with hop counts (None for
+// unreachable nodes) and order with the nodes in the order they were reached.
+fn bfs(succ: &[Vec<usize>], source: usize, dist: &mut [Option<usize>], order: &mut Vec<usize>) {
+    for d in dist.iter_mut() {
+        *d = None;
+    }
+    order.clear();
+    let mut queue = VecDeque::new();
+    dist[source] = Some(0);
+    queue.push_back(source);
+    while let Some(u) = queue.pop_front() {
+        order.push(u);
+        let next = dist[u].map(|d| d + 1);
+        for &v in &succ[u] {
+            if dist[v].is_none() {
+                dist[v] = next;
+                queue.push_back(v);
+            }
+        }
+    }
+}
+
+// Brandes' algorithm for the sources given, adding each node's dependency to
+// scores. Parallel edges count as separate shortest paths.
+fn accumulate_betweenness(succ: &[Vec<usize>], sources: &[usize], scores: &mut [f64]) {
+    let n = succ.len();
+    let mut dist = vec![None; n];
+    let mut order = Vec::with_capacity(n);
+    let mut sigma = vec![0.0; n];
+    let mut delta = vec![0.0; n];
+    let mut pred: Vec<Vec<usize>> = vec![Vec::new(); n];
+
+    for &s in sources {
+        bfs(succ, s, &mut dist, &mut order);
+        for &v in &order {
+            sigma[v] = 0.0;
+            delta[v] = 0.0;
+            pred[v].clear();
+        }
+        sigma[s] = 1.0;
+        for &u in &order {
+            for &v in &succ[u] {
+                if dist[v] == dist[u].map(|d| d + 1) {
+                    sigma[v] += sigma[u];
+                    pred[v].push(u);
+                }
+            }
+        }
+        for &w in order.iter().rev() {
+            for &v in &pred[w] {
+                delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+            }
+            if w != s {
+                scores[w] += delta[w];
+            }
+        }
+    }
+}
+
+// Scale that turns raw directed betweenness into a fraction of all ordered
+// pairs of other nodes.
+fn betweenness_scale(n: usize, normalized: bool) -> f64 {
+    if normalized && n > 2 {
+        1.0 / ((n - 1) * (n - 2)) as f64
+    } else {
+        1.0
+    }
+}
+
+// Computes exact betweenness centrality with Brandes' algorithm in
+// O(order * size) time: for every node, the number of shortest paths between
+// other nodes that pass through it, each pair's paths sharing one unit.
+// With normalized the scores are divided by the number of ordered pairs.
+pub fn betweenness<N, E, G>(graph: &G, normalized: bool) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let sources: Vec<usize> = (0..n).collect();
+    let mut scores = vec![0.0; n];
+    accumulate_betweenness(&dense.succ, &sources, &mut scores);
+    let scale = betweenness_scale(n, normalized);
+    dense.scores(scores.into_iter().map(|x| x * scale).collect())
+}
+
+// Estimates betweenness centrality from shortest paths out of a random sample
+// of pivots sources, scaled up to the whole graph. With pivots >= order this
+// is the exact result. The same rng_seed always picks the same pivots.
+pub fn sampled_betweenness<N, E, G>(graph: &G, pivots: usize, normalized: bool, rng_seed: u64)
+    -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let k = pivots.min(n);
+
+    // Partial Fisher-Yates shuffle: the first k entries are the sample
+    let mut sources: Vec<usize> = (0..n).collect();
+    let mut rng = Rng::new(rng_seed);
+    for i in 0..k {
+        let j = i + rng.below(n - i);
+        sources.swap(i, j);
+    }
+    sources.truncate(k);
+
+    let mut scores = vec![0.0; n];
+    accumulate_betweenness(&dense.succ, &sources, &mut scores);
+    let scale = betweenness_scale(n, normalized) * if k > 0 { n as f64 / k as f64 } else { 0.0 };
+    dense.scores(scores.into_iter().map(|x| x * scale).collect())
+}
+
+// Runs a BFS from every node and folds the distances to the nodes it reaches
+// (other than itself) into a score.
+fn distance_based<N, E, G, F>(graph: &G, score: F) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>, F: Fn(usize, &[usize]) -> f64
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let mut dist = vec![None; n];
+    let mut order = Vec::with_capacity(n);
+    let mut reached = Vec::with_capacity(n);
+    let mut scores = Vec::with_capacity(n);
+    for s in 0..n {
+        bfs(&dense.succ, s, &mut dist, &mut order);
+        reached.clear();
+        reached.extend(order.iter().skip(1).filter_map(|&v| dist[v]));
+        scores.push(score(n, &reached));
+    }
+    dense.scores(scores)
+}
+
+// Computes closeness centrality from outgoing distances: the inverse of the
+// average distance to the nodes a node can reach, scaled by the fraction of
+// the graph it can reach (Wasserman and Faust), so that nodes reaching only
+// a few close neighbors do not come out on top. A node that reaches nothing
+// scores zero.
+pub fn closeness<N, E, G>(graph: &G) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    distance_based(graph, |n, reached| {
+        let total: usize = reached.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let r = reached.len() as f64;
+        (r / (n - 1) as f64) * (r / total as f64)
+    })
+}
+
+// Computes harmonic centrality: the sum of the inverse distances from a node
+// to every other node, where unreachable nodes add nothing.
+pub fn harmonic<N, E, G>(graph: &G) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    distance_based(graph, |_, reached| reached.iter().map(|&d| 1.0 / d as f64).sum())
+}
+
+// Scales values to unit Euclidean length, unless they are all zero.
+fn normalize_l2(values: &mut [f64]) {
+    let norm = values.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        for x in values.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+// Computes eigenvector centrality by power iteration: a node is as central
+// as the sum of the centralities of its predecessors. Iterates on A + I,
+// which has the same leading eigenvector but also converges on periodic
+// graphs such as directed cycles. The scores have unit Euclidean length.
+pub fn eigenvector<N, E, G>(graph: &G, tolerance: f64, max_iterations: usize) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let mut x = vec![1.0; n];
+    normalize_l2(&mut x);
+    for _ in 0..max_iterations {
+        let mut next = x.clone();
+        for (u, succ) in dense.succ.iter().enumerate() {
+            for &v in succ {
+                next[v] += x[u];
+            }
+        }
+        normalize_l2(&mut next);
+        let delta: f64 = x.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        x = next;
+        if delta < n as f64 * tolerance {
+            break;
+        }
+    }
+    dense.scores(x)
+}
+
+// Computes Katz centrality, the fixed point of x = alpha * A^T x + beta: every
+// node gets beta for free plus alpha times the centrality of each
+// predecessor. Converges when alpha is below the inverse of the largest
+// eigenvalue of the adjacency matrix; the scores are left unnormalized.
+pub fn katz<N, E, G>(graph: &G, alpha: f64, beta: f64, tolerance: f64, max_iterations: usize)
+    -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let mut x = vec![0.0; n];
+    for _ in 0..max_iterations {
+        let mut next = vec![beta; n];
+        for (u, succ) in dense.succ.iter().enumerate() {
+            for &v in succ {
+                next[v] += alpha * x[u];
+            }
+        }
+        let delta: f64 = x.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        x = next;
+        if delta < n as f64 * tolerance {
+            break;
+        }
+    }
+    dense.scores(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+    use io;
+
+    fn from_edges(n: usize, edges: &[(usize, usize)]) -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let ids: Vec<i64> = (0..n).map(|_| g.add_node(())).collect();
+        for &(a, b) in edges {
+            g.add_edge(ids[a], ids[b], ());
+        }
+        (g, ids)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn betweenness_on_paths_and_stars() {
+        let (g, n) = from_edges(4, &[(0, 1), (1, 2), (2, 3)]);
+        let scores = betweenness(&g, false);
+        let raw: Vec<f64> = n.iter().map(|v| scores[v]).collect();
+        assert_eq!(raw, vec![0.0, 2.0, 2.0, 0.0]);
+        assert!(close(betweenness(&g, true)[&n[1]], 2.0 / 6.0));
+
+        // Two equal shortest paths 0 -> 1 -> 3 and 0 -> 2 -> 3 split the pair.
+        let (g, n) = from_edges(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let scores = betweenness(&g, false);
+        assert!(close(scores[&n[1]], 0.5) && close(scores[&n[2]], 0.5));
+        assert_eq!(sampled_betweenness(&g, 10, false, 1), scores);
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        assert!(betweenness(&twitter, true).values().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn closeness_and_harmonic() {
+        let (g, n) = from_edges(4, &[(0, 1), (1, 2), (2, 3)]);
+        let c = closeness(&g);
+        assert!(close(c[&n[0]], 3.0 / 6.0));
+        assert!(close(c[&n[2]], (1.0 / 3.0) * 1.0));
+        assert_eq!(c[&n[3]], 0.0);
+
+        let h = harmonic(&g);
+        assert!(close(h[&n[0]], 1.0 + 0.5 + 1.0 / 3.0));
+        assert!(close(h[&n[2]], 1.0));
+        assert_eq!(h[&n[3]], 0.0);
+    }
+
+    #[test]
+    fn eigenvector_and_katz() {
+        let (g, n) = from_edges(3, &[(0, 1), (1, 2), (2, 0)]);
+        let e = eigenvector(&g, 1e-12, 1000);
+        for v in &n {
+            assert!(close(e[v], 1.0 / 3f64.sqrt()));
+        }
+
+        // 1 and 2 both point at 0, 0 points at 3
+        let (g, n) = from_edges(4, &[(1, 0), (2, 0), (0, 3)]);
+        let k = katz(&g, 0.1, 1.0, 1e-12, 1000);
+        assert!(close(k[&n[1]], 1.0));
+        assert!(close(k[&n[0]], 1.2));
+        assert!(close(k[&n[3]], 1.12));
+    }
+}
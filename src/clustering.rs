@@ -0,0 +1,237 @@
+use graph::Graph;
+use ranking::Dense;
+use std::collections::HashMap;
+
+// Undirected triangle counts treat every edge as a link between two distinct
+// nodes: direction is ignored, parallel edges count once and self-loops are
+// skipped. A triangle is a set of three nodes that are pairwise linked.
+
+// Sorted, duplicate-free neighbor lists with direction ignored.
+fn undirected(dense: &Dense<impl Copy>) -> Vec<Vec<usize>> {
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); dense.nodes.len()];
+    for (u, succ) in dense.succ.iter().enumerate() {
+        for &v in succ {
+            if u != v {
+                adj[u].push(v);
+                adj[v].push(u);
+            }
+        }
+    }
+    for list in &mut adj {
+        list.sort_unstable();
+        list.dedup();
+    }
+    adj
+}
+
+// Number of common entries of two sorted lists
+fn count_common(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            count += 1;
+            i += 1;
+            j += 1;
+        }
+    }
+    count
+}
+
+fn node_iterator(adj: &[Vec<usize>]) -> Vec<usize> {
+    adj.iter()
+        .map(|neighbors| {
+            let mut count = 0;
+            for (i, &u) in neighbors.iter().enumerate() {
+                for &w in &neighbors[i + 1..] {
+                    if adj[u].binary_search(&w).is_ok() {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        })
+        .collect()
+}
+
+// Counts the triangles through each node with the node-iterator algorithm,
+// which checks every pair of a node's neighbors for a link. Takes time
+// proportional to the sum of squared degrees.
+pub fn node_triangles<N, E, G>(graph: &G) -> HashMap<G::TIndex, usize>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let adj = undirected(&dense);
+    dense.scores(node_iterator(&adj))
+}
+
+// Counts the triangles in the graph with the edge-iterator algorithm. Every
+// link is oriented from the endpoint of lower degree to the one of higher
+// degree, and each triangle is then found exactly once by intersecting the
+// forward neighbors of a link's endpoints. Runs in O(size^1.5).
+pub fn count_triangles<N, E, G>(graph: &G) -> usize
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let adj = undirected(&dense);
+    let rank = |v: usize| (adj[v].len(), v);
+    let forward: Vec<Vec<usize>> = adj.iter()
+        .enumerate()
+        .map(|(u, neighbors)| neighbors.iter().cloned().filter(|&v| rank(u) < rank(v)).collect())
+        .collect();
+
+    let mut count = 0;
+    for out in &forward {
+        for &v in out {
+            count += count_common(out, &forward[v]);
+        }
+    }
+    count
+}
+
+// Counts of the two kinds of directed triangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DirectedTriangles {
+    // Triples with a -> b -> c -> a
+    pub cycles: usize,
+
+    // Triples with a -> b, b -> c and a -> c
+    pub transitive: usize,
+}
+
+// Counts directed triangles, respecting edge direction. A set of three nodes
+// can hold several of them: with edges both ways between every pair it has
+// two cycles and six transitive triples. Parallel edges count once.
+pub fn directed_triangles<N, E, G>(graph: &G) -> DirectedTriangles
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let adj = undirected(&dense);
+    let mut succ = dense.succ.clone();
+    for list in &mut succ {
+        list.sort_unstable();
+        list.dedup();
+    }
+    let arc = |a: usize, b: usize| succ[a].binary_search(&b).is_ok();
+
+    let mut counts = DirectedTriangles::default();
+    for (a, neighbors) in adj.iter().enumerate() {
+        for &b in neighbors.iter().filter(|&&b| b > a) {
+            for &c in adj[b].iter().filter(|&&c| c > b) {
+                if adj[a].binary_search(&c).is_err() {
+                    continue;
+                }
+                if arc(a, b) && arc(b, c) && arc(c, a) {
+                    counts.cycles += 1;
+                }
+                if arc(a, c) && arc(c, b) && arc(b, a) {
+                    counts.cycles += 1;
+                }
+                for &(x, y, z) in &[(a, b, c), (a, c, b), (b, a, c), (b, c, a), (c, a, b), (c, b, a)] {
+                    if arc(x, y) && arc(y, z) && arc(x, z) {
+                        counts.transitive += 1;
+                    }
+                }
+            }
+        }
+    }
+    counts
+}
+
+// Computes each node's local clustering coefficient: the fraction of pairs
+// of its neighbors that are linked themselves. Nodes with fewer than two
+// neighbors score zero.
+pub fn local_clustering<N, E, G>(graph: &G) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let adj = undirected(&dense);
+    let triangles = node_iterator(&adj);
+    let coefficients = adj.iter()
+        .zip(triangles)
+        .map(|(neighbors, t)| {
+            let d = neighbors.len();
+            if d < 2 { 0.0 } else { t as f64 / (d * (d - 1) / 2) as f64 }
+        })
+        .collect();
+    dense.scores(coefficients)
+}
+
+// Computes the global clustering coefficient as the average of the local
+// coefficients over all nodes, or zero for an empty graph.
+pub fn average_clustering<N, E, G>(graph: &G) -> f64
+    where G: Graph<N, E>
+{
+    let local = local_clustering(graph);
+    if local.is_empty() {
+        return 0.0;
+    }
+    local.values().sum::<f64>() / local.len() as f64
+}
+
+// Computes the transitivity: three times the number of triangles over the
+// number of connected triples, i.e. the chance that two neighbors of a node
+// are linked, weighted towards high-degree nodes.
+pub fn transitivity<N, E, G>(graph: &G) -> f64
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let adj = undirected(&dense);
+    let triples: usize = adj.iter().map(|n| n.len() * n.len().saturating_sub(1) / 2).sum();
+    if triples == 0 {
+        return 0.0;
+    }
+    let triangles: usize = node_iterator(&adj).iter().sum::<usize>() / 3;
+    3.0 * triangles as f64 / triples as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    // Two triangles {0, 1, 2} and {1, 2, 3} sharing the link 1-2, plus a
+    // pendant node 4 hanging off 3.
+    fn kite() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..5).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (1, 2), (2, 0), (1, 3), (3, 2), (2, 1), (3, 4), (4, 4)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn triangle_counts_agree() {
+        let (g, n) = kite();
+        let per_node = node_triangles(&g);
+        let counts: Vec<usize> = n.iter().map(|v| per_node[v]).collect();
+        assert_eq!(counts, vec![1, 2, 2, 1, 0]);
+        assert_eq!(count_triangles(&g), 2);
+        assert_eq!(counts.iter().sum::<usize>(), 3 * count_triangles(&g));
+
+        // {0, 1, 2} holds the cycle 0 -> 1 -> 2 -> 0 and, thanks to 2 -> 1,
+        // the transitive triple 2 -> 0 -> 1. {1, 2, 3} holds 1 -> 3 -> 2 -> 1
+        // and 1 -> 3 -> 2 with 1 -> 2.
+        let directed = directed_triangles(&g);
+        assert_eq!(directed, DirectedTriangles { cycles: 2, transitive: 2 });
+    }
+
+    #[test]
+    fn clustering_coefficients() {
+        let (g, n) = kite();
+        let local = local_clustering(&g);
+        assert_eq!(local[&n[0]], 1.0);
+        assert!((local[&n[1]] - 2.0 / 3.0).abs() < 1e-12);
+        assert!((local[&n[3]] - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(local[&n[4]], 0.0);
+        let expected = (1.0 + 2.0 / 3.0 + 2.0 / 3.0 + 1.0 / 3.0) / 5.0;
+        assert!((average_clustering(&g) - expected).abs() < 1e-12);
+
+        // Triples: 1 + 3 + 3 + 3 + 0 = 10, triangles: 2
+        assert!((transitivity(&g) - 0.6).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,368 @@
+use adjlistgraph::AdjListGraph;
+use graph::Graph;
+use rng::Rng;
+use std::collections::HashMap;
+
+// Community detection works on the undirected view of the graph: an edge
+// links its endpoints both ways, parallel edges add up their weights and a
+// self-loop adds its weight to its node's own community. Partitions map every
+// node to a community number; the detection routines number communities
+// 0, 1, 2, ... without gaps.
+
+// Weighted undirected adjacency over nodes renumbered to 0..n. Every link
+// appears in the lists of both endpoints; self-loops are kept apart.
+struct Weighted {
+    adj: Vec<Vec<(usize, f64)>>,
+    loops: Vec<f64>,
+}
+
+impl Weighted {
+    fn new<N, E, G, F>(graph: &G, nodes: &[G::TIndex], mut weight: F) -> Weighted
+        where G: Graph<N, E>, F: FnMut(&E) -> f64
+    {
+        let slot: HashMap<G::TIndex, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut adj = vec![Vec::new(); nodes.len()];
+        let mut loops = vec![0.0; nodes.len()];
+        for edge in graph.edges() {
+            let (start, end) = match graph.get_nodes_of(edge) {
+                Some((start, end)) => (slot[&start], slot[&end]),
+                None => continue,
+            };
+            let w = graph.get_edge_data(edge).map_or(0.0, &mut weight);
+            if start == end {
+                loops[start] += w;
+            } else {
+                adj[start].push((end, w));
+                adj[end].push((start, w));
+            }
+        }
+        Weighted { adj, loops }
+    }
+
+    fn degree(&self, u: usize) -> f64 {
+        self.adj[u].iter().map(|&(_, w)| w).sum::<f64>() + 2.0 * self.loops[u]
+    }
+
+    // Twice the total edge weight
+    fn total_degree(&self) -> f64 {
+        (0..self.adj.len()).map(|u| self.degree(u)).sum()
+    }
+
+    // Collapses every community into one node. Community numbers must run
+    // from 0 to count - 1.
+    fn aggregate(&self, community: &[usize], count: usize) -> Weighted {
+        let mut between: Vec<HashMap<usize, f64>> = vec![HashMap::new(); count];
+        let mut loops = vec![0.0; count];
+        for (u, list) in self.adj.iter().enumerate() {
+            let cu = community[u];
+            loops[cu] += self.loops[u];
+            for &(v, w) in list {
+                let cv = community[v];
+                if cu == cv {
+                    // Seen once from each endpoint
+                    loops[cu] += w / 2.0;
+                } else {
+                    *between[cu].entry(cv).or_insert(0.0) += w;
+                }
+            }
+        }
+        Weighted {
+            adj: between.into_iter().map(|m| m.into_iter().collect()).collect(),
+            loops,
+        }
+    }
+}
+
+// Renumbers labels to 0..count in order of first appearance.
+fn renumber(labels: &mut [usize]) -> usize {
+    let mut fresh = HashMap::new();
+    for label in labels.iter_mut() {
+        let next = fresh.len();
+        *label = *fresh.entry(*label).or_insert(next);
+    }
+    fresh.len()
+}
+
+// Computes the modularity of a partition: the fraction of edge weight inside
+// communities minus the fraction expected if edges were placed at random
+// with the same degrees. Ranges from -1/2 to 1; higher means more clear-cut
+// communities. Nodes missing from the partition count as singletons.
+pub fn modularity<N, E, G, F>(graph: &G, partition: &HashMap<G::TIndex, usize>, weight: F) -> f64
+    where G: Graph<N, E>, F: FnMut(&E) -> f64
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let weighted = Weighted::new(graph, &nodes, weight);
+    let mut community: Vec<usize> = nodes.iter()
+        .enumerate()
+        .map(|(i, n)| partition.get(n).map_or(2 * i + 1, |&c| 2 * c))
+        .collect();
+    let count = renumber(&mut community);
+    weighted_modularity(&weighted, &community, count)
+}
+
+fn weighted_modularity(weighted: &Weighted, community: &[usize], count: usize) -> f64 {
+    let m2 = weighted.total_degree();
+    if m2 == 0.0 {
+        return 0.0;
+    }
+    let mut inside = vec![0.0; count];
+    let mut total = vec![0.0; count];
+    for (u, list) in weighted.adj.iter().enumerate() {
+        let c = community[u];
+        total[c] += weighted.degree(u);
+        inside[c] += 2.0 * weighted.loops[u];
+        inside[c] += list.iter().filter(|&&(v, _)| community[v] == c).map(|&(_, w)| w).sum::<f64>();
+    }
+    inside.iter().zip(&total).map(|(&i, &t)| i / m2 - (t / m2) * (t / m2)).sum()
+}
+
+// Detects communities by asynchronous label propagation. Every node starts in
+// its own community; then, visiting nodes in random order, each one joins the
+// community with the largest total edge weight among its neighbors, breaking
+// ties at random, until no node wants to move or max_rounds rounds have
+// passed. Fast, but the result depends on rng_seed.
+pub fn label_propagation<N, E, G, F>(graph: &G, weight: F, max_rounds: usize, rng_seed: u64)
+    -> HashMap<G::TIndex, usize>
+    where G: Graph<N, E>, F: FnMut(&E) -> f64
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let weighted = Weighted::new(graph, &nodes, weight);
+    let n = nodes.len();
+    let mut label: Vec<usize> = (0..n).collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    let mut rng = Rng::new(rng_seed);
+    let mut score: HashMap<usize, f64> = HashMap::new();
+    let mut best = Vec::new();
+
+    for _ in 0..max_rounds {
+        for i in (1..n).rev() {
+            order.swap(i, rng.below(i + 1));
+        }
+        let mut changed = false;
+        for &u in &order {
+            score.clear();
+            for &(v, w) in &weighted.adj[u] {
+                *score.entry(label[v]).or_insert(0.0) += w;
+            }
+            let top = score.values().cloned().fold(f64::NEG_INFINITY, f64::max);
+            best.clear();
+            best.extend(score.iter().filter(|&(_, &s)| s == top).map(|(&l, _)| l));
+            if best.is_empty() || best.contains(&label[u]) {
+                continue;
+            }
+            best.sort_unstable();
+            label[u] = best[rng.below(best.len())];
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    renumber(&mut label);
+    nodes.into_iter().zip(label).collect()
+}
+
+// One pass of Louvain's local moving phase: nodes move to the neighboring
+// community with the best modularity gain until none can improve. Returns
+// whether any node moved.
+fn move_nodes(weighted: &Weighted, community: &mut [usize]) -> bool {
+    let n = weighted.adj.len();
+    let m2 = weighted.total_degree();
+    let degree: Vec<f64> = (0..n).map(|u| weighted.degree(u)).collect();
+    let mut total = degree.clone();
+    let mut links: HashMap<usize, f64> = HashMap::new();
+    let mut moved = false;
+
+    loop {
+        let mut improved = false;
+        for u in 0..n {
+            let old = community[u];
+            links.clear();
+            links.insert(old, 0.0);
+            for &(v, w) in &weighted.adj[u] {
+                *links.entry(community[v]).or_insert(0.0) += w;
+            }
+            total[old] -= degree[u];
+
+            // Gain of joining c, up to a constant factor shared by all c
+            let gain = |c: usize, k: f64| k - total[c] * degree[u] / m2;
+            let mut best = (old, gain(old, links[&old]));
+            for (&c, &k) in &links {
+                let g = gain(c, k);
+                if g > best.1 + 1e-12 {
+                    best = (c, g);
+                }
+            }
+
+            total[best.0] += degree[u];
+            if best.0 != old {
+                community[u] = best.0;
+                improved = true;
+                moved = true;
+            }
+        }
+        if !improved {
+            return moved;
+        }
+    }
+}
+
+// Detects communities with the Louvain method: nodes greedily move to the
+// neighboring community that raises modularity most, then communities are
+// collapsed into single nodes and the process repeats on the smaller graph
+// until nothing moves. Ties keep a node where it is.
+pub fn louvain<N, E, G, F>(graph: &G, weight: F) -> HashMap<G::TIndex, usize>
+    where G: Graph<N, E>, F: FnMut(&E) -> f64
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let mut weighted = Weighted::new(graph, &nodes, weight);
+    let mut membership: Vec<usize> = (0..nodes.len()).collect();
+    if weighted.total_degree() == 0.0 {
+        return nodes.into_iter().zip(membership).collect();
+    }
+
+    loop {
+        let mut community: Vec<usize> = (0..weighted.adj.len()).collect();
+        if !move_nodes(&weighted, &mut community) {
+            break;
+        }
+        let count = renumber(&mut community);
+        for m in membership.iter_mut() {
+            *m = community[*m];
+        }
+        weighted = weighted.aggregate(&community, count);
+    }
+
+    renumber(&mut membership);
+    nodes.into_iter().zip(membership).collect()
+}
+
+// A graph with one node per community, together with the index of the node
+// standing for each community number.
+pub type CommunityGraph<TIndex> = (AdjListGraph<Vec<TIndex>, f64>, HashMap<usize, i64>);
+
+// Collapses each community of a partition into one node carrying its
+// members. An edge from community a to b carries the total weight of the
+// original edges from a to b; edges inside a community become a self-loop.
+pub fn community_graph<N, E, G, F>(graph: &G, partition: &HashMap<G::TIndex, usize>, mut weight: F)
+    -> CommunityGraph<G::TIndex>
+    where G: Graph<N, E>, F: FnMut(&E) -> f64
+{
+    let mut numbers: Vec<usize> = partition.values().cloned().collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let mut aggregated = AdjListGraph::with_parallel_edges(false);
+    let mut node_of = HashMap::with_capacity(numbers.len());
+    for &c in &numbers {
+        node_of.insert(c, aggregated.add_node(Vec::new()));
+    }
+    for node in graph.nodes() {
+        if let Some(members) = partition.get(&node).and_then(|c| aggregated.get_node_data_mut(node_of[c])) {
+            members.push(node);
+        }
+    }
+
+    for edge in graph.edges() {
+        let ends = graph.get_nodes_of(edge)
+            .and_then(|(s, e)| Some((*node_of.get(partition.get(&s)?)?, *node_of.get(partition.get(&e)?)?)));
+        let (start, end) = match ends {
+            Some(ends) => ends,
+            None => continue,
+        };
+        let w = graph.get_edge_data(edge).map_or(0.0, &mut weight);
+        match aggregated.get_edge(start, end) {
+            Some(existing) => {
+                if let Some(total) = aggregated.get_edge_data_mut(existing) {
+                    *total += w;
+                }
+            }
+            None => {
+                aggregated.add_edge(start, end, w);
+            }
+        }
+    }
+    (aggregated, node_of)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two 4-cliques {0..3} and {4..7} joined by the single edge 3 -> 4
+    fn two_cliques() -> (AdjListGraph<(), f64>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..8).map(|_| g.add_node(())).collect();
+        for base in &[0, 4] {
+            for a in 0..4 {
+                for b in a + 1..4 {
+                    g.add_edge(n[base + a], n[base + b], 1.0);
+                }
+            }
+        }
+        g.add_edge(n[3], n[4], 1.0);
+        (g, n)
+    }
+
+    fn split_in_halves(partition: &HashMap<i64, usize>, n: &[i64]) -> bool {
+        n[..4].iter().all(|v| partition[v] == partition[&n[0]])
+            && n[4..].iter().all(|v| partition[v] == partition[&n[4]])
+            && partition[&n[0]] != partition[&n[4]]
+    }
+
+    #[test]
+    fn modularity_of_partitions() {
+        let (g, n) = two_cliques();
+        let one: HashMap<i64, usize> = n.iter().map(|&v| (v, 0)).collect();
+        assert!(modularity(&g, &one, |&w| w).abs() < 1e-12);
+
+        let halves: HashMap<i64, usize> = n.iter().enumerate().map(|(i, &v)| (v, i / 4)).collect();
+        let expected = 2.0 * (6.0 / 13.0 - (13.0 / 26.0) * (13.0 / 26.0));
+        assert!((modularity(&g, &halves, |&w| w) - expected).abs() < 1e-12);
+        assert!(modularity(&g, &HashMap::new(), |&w| w) < 0.0);
+    }
+
+    #[test]
+    fn louvain_and_label_propagation_find_cliques() {
+        let (g, n) = two_cliques();
+        let partition = louvain(&g, |&w| w);
+        assert!(split_in_halves(&partition, &n));
+        assert!(partition.values().all(|&c| c < 2));
+
+        let partition = label_propagation(&g, |&w| w, 100, 3);
+        assert!(split_in_halves(&partition, &n));
+
+        // Weights decide where a path splits: 0 =10= 1 -1- 2 =10= 3
+        let mut path = AdjListGraph::new();
+        let p: Vec<i64> = (0..4).map(|_| path.add_node(())).collect();
+        for &(a, b, w) in &[(0, 1, 10.0), (1, 2, 1.0), (2, 3, 10.0)] {
+            path.add_edge(p[a], p[b], w);
+        }
+        let partition = louvain(&path, |&w| w);
+        assert_eq!(partition[&p[0]], partition[&p[1]]);
+        assert_eq!(partition[&p[2]], partition[&p[3]]);
+        assert!(partition[&p[1]] != partition[&p[2]]);
+    }
+
+    #[test]
+    fn aggregated_community_graph() {
+        let (g, n) = two_cliques();
+        let partition = louvain(&g, |&w| w);
+        let (aggregated, node_of) = community_graph(&g, &partition, |&w| w);
+        assert_eq!(aggregated.order(), 2);
+        let a = node_of[&partition[&n[0]]];
+        let b = node_of[&partition[&n[4]]];
+        assert_eq!(aggregated.get_node_data(a).unwrap().len(), 4);
+        let inside = aggregated.get_edge(a, a).unwrap();
+        assert_eq!(aggregated.get_edge_data(inside), Some(&6.0));
+        let between = aggregated.get_edge(a, b).unwrap();
+        assert_eq!(aggregated.get_edge_data(between), Some(&1.0));
+
+        // Louvain on the aggregated graph keeps the two communities apart,
+        // at the same modularity as the partition they came from.
+        let collapsed = louvain(&aggregated, |&w| w);
+        assert!(collapsed[&a] != collapsed[&b]);
+        assert!((modularity(&aggregated, &collapsed, |&w| w) - modularity(&g, &partition, |&w| w)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,383 @@
+use adjlistgraph::AdjListGraph;
+use graph::{Graph, GraphError};
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use traversal::{Direction, Dfs, Event};
+use unionfind::UnionFind;
+
+// A node on Tarjan's explicit call stack together with its remaining
+// successors.
+type Frame<'a, TIndex> = (TIndex, Box<dyn Iterator<Item = TIndex> + 'a>);
+
+// Finds the strongly connected components with Tarjan's algorithm. The
+// depth-first search keeps its own stack instead of recursing, so long
+// chains cannot overflow the thread's stack. Components come out in reverse
+// topological order: no component has an edge to one listed after it.
+pub fn tarjan_scc<N, E, G>(graph: &G) -> Vec<Vec<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let mut components = Vec::new();
+    let mut index: HashMap<G::TIndex, usize> = HashMap::with_capacity(graph.order());
+    let mut lowlink: HashMap<G::TIndex, usize> = HashMap::with_capacity(graph.order());
+    let mut on_stack = HashSet::new();
+    let mut stack = Vec::new();
+    let mut call_stack: Vec<Frame<G::TIndex>> = Vec::new();
+
+    for root in graph.nodes() {
+        if index.contains_key(&root) {
+            continue;
+        }
+
+        index.insert(root, index.len());
+        lowlink.insert(root, index[&root]);
+        stack.push(root);
+        on_stack.insert(root);
+        call_stack.push((root, graph.get_succ_nodes_of(root)));
+
+        while let Some(&mut (node, ref mut succ)) = call_stack.last_mut() {
+            match succ.next() {
+                Some(next) if !index.contains_key(&next) => {
+                    index.insert(next, index.len());
+                    lowlink.insert(next, index[&next]);
+                    stack.push(next);
+                    on_stack.insert(next);
+                    call_stack.push((next, graph.get_succ_nodes_of(next)));
+                }
+                Some(next) => {
+                    if on_stack.contains(&next) && index[&next] < lowlink[&node] {
+                        lowlink.insert(node, index[&next]);
+                    }
+                }
+                None => {
+                    call_stack.pop();
+                    let low = lowlink[&node];
+                    if let Some(&(parent, _)) = call_stack.last() {
+                        if low < lowlink[&parent] {
+                            lowlink.insert(parent, low);
+                        }
+                    }
+                    if low == index[&node] {
+                        let mut component = Vec::new();
+                        while let Some(member) = stack.pop() {
+                            on_stack.remove(&member);
+                            component.push(member);
+                            if member == node {
+                                break;
+                            }
+                        }
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
+
+// Finds the strongly connected components with Kosaraju's algorithm: one
+// depth-first pass records finishing order, and a second pass over the
+// reversed edges peels off one component per start node. Components come out
+// in topological order, the reverse of tarjan_scc.
+pub fn kosaraju_scc<N, E, G>(graph: &G) -> Vec<Vec<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let first = match graph.nodes().next() {
+        Some(node) => node,
+        None => return Vec::new(),
+    };
+
+    let mut finished = Vec::with_capacity(graph.order());
+    let mut dfs = Dfs::new(graph, first, Direction::Outgoing);
+    for start in iter::once(first).chain(graph.nodes()) {
+        dfs.start_from(start);
+        while let Some(event) = dfs.next_event() {
+            if let Event::Finish(node, _) = event {
+                finished.push(node);
+            }
+        }
+    }
+
+    let mut components = Vec::new();
+    let mut dfs = Dfs::new(graph, finished[finished.len() - 1], Direction::Incoming);
+    for &start in finished.iter().rev() {
+        dfs.start_from(start);
+        let component: Vec<_> = dfs.by_ref().map(|(node, _)| node).collect();
+        if !component.is_empty() {
+            components.push(component);
+        }
+    }
+    components
+}
+
+// Collapses every strongly connected component into a single node carrying
+// its members. Nodes are added in topological order, so in the returned graph
+// every edge runs from a lower index to a higher one. An edge's data counts
+// the original edges it stands for; edges within a component are dropped.
+pub fn condensation<N, E, G>(graph: &G) -> AdjListGraph<Vec<G::TIndex>, usize>
+    where G: Graph<N, E>
+{
+    let mut dag = AdjListGraph::with_parallel_edges(false);
+    let mut component_of = HashMap::with_capacity(graph.order());
+    for members in tarjan_scc(graph).into_iter().rev() {
+        let id = dag.add_node(Vec::new());
+        for &member in &members {
+            component_of.insert(member, id);
+        }
+        if let Some(data) = dag.get_node_data_mut(id) {
+            *data = members;
+        }
+    }
+
+    for edge in graph.edges() {
+        let (start, end) = match graph.get_nodes_of(edge) {
+            Some((start, end)) => (component_of[&start], component_of[&end]),
+            None => continue,
+        };
+        if start == end {
+            continue;
+        }
+        match dag.get_edge(start, end) {
+            Some(existing) => {
+                if let Some(count) = dag.get_edge_data_mut(existing) {
+                    *count += 1;
+                }
+            }
+            None => {
+                dag.add_edge(start, end, 1);
+            }
+        }
+    }
+    dag
+}
+
+// Finds the weakly connected components, i.e. the components of the graph
+// with edge direction ignored.
+pub fn weakly_connected_components<N, E, G>(graph: &G) -> Vec<Vec<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let slot: HashMap<G::TIndex, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let mut sets = UnionFind::new(nodes.len());
+    for edge in graph.edges() {
+        if let Some((start, end)) = graph.get_nodes_of(edge) {
+            sets.union(slot[&start], slot[&end]);
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<G::TIndex>> = HashMap::with_capacity(sets.set_count());
+    for (i, &node) in nodes.iter().enumerate() {
+        members.entry(sets.find(i)).or_default().push(node);
+    }
+    members.into_values().collect()
+}
+
+/**
+ * An AdjListGraph that keeps its weakly connected components up to date as
+ * nodes and edges are added, at near-constant cost per insertion. Removals
+ * would split components, which a union-find cannot undo, so the graph is
+ * only handed out read-only.
+ */
+#[derive(Debug)]
+pub struct IncrementalComponents<TNode, TEdge> {
+    graph: AdjListGraph<TNode, TEdge>,
+    sets: UnionFind,
+
+    // Maps nodes to their union-find element and back
+    slot: HashMap<i64, usize>,
+    node_at: Vec<i64>,
+}
+
+impl<TNode, TEdge> Default for IncrementalComponents<TNode, TEdge> {
+    fn default() -> IncrementalComponents<TNode, TEdge> {
+        IncrementalComponents::new()
+    }
+}
+
+impl<TNode, TEdge> IncrementalComponents<TNode, TEdge> {
+    pub fn new() -> IncrementalComponents<TNode, TEdge> {
+        IncrementalComponents::from_graph(AdjListGraph::new())
+    }
+
+    // Takes over an existing graph, computing its components once.
+    pub fn from_graph(graph: AdjListGraph<TNode, TEdge>) -> IncrementalComponents<TNode, TEdge> {
+        let node_at: Vec<i64> = graph.nodes().collect();
+        let slot = node_at.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut tracked = IncrementalComponents {
+            sets: UnionFind::new(node_at.len()),
+            graph,
+            slot,
+            node_at,
+        };
+        let edges: Vec<(i64, i64)> = tracked.graph.edges()
+            .filter_map(|e| tracked.graph.get_nodes_of(e))
+            .collect();
+        for (start, end) in edges {
+            tracked.sets.union(tracked.slot[&start], tracked.slot[&end]);
+        }
+        tracked
+    }
+
+    pub fn graph(&self) -> &AdjListGraph<TNode, TEdge> {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> AdjListGraph<TNode, TEdge> {
+        self.graph
+    }
+
+    pub fn try_add_node(&mut self, data: TNode) -> Result<i64, GraphError> {
+        let node = self.graph.try_add_node(data)?;
+        self.slot.insert(node, self.sets.make_set());
+        self.node_at.push(node);
+        Ok(node)
+    }
+
+    pub fn add_node(&mut self, data: TNode) -> i64 {
+        self.try_add_node(data).expect("failed to add node")
+    }
+
+    // Adds an edge and merges the components of its endpoints.
+    pub fn try_add_edge(&mut self, start: i64, end: i64, data: TEdge) -> Result<i64, GraphError> {
+        let edge = self.graph.try_add_edge(start, end, data)?;
+        self.sets.union(self.slot[&start], self.slot[&end]);
+        Ok(edge)
+    }
+
+    pub fn add_edge(&mut self, start: i64, end: i64, data: TEdge) -> Option<i64> {
+        self.try_add_edge(start, end, data).ok()
+    }
+
+    // Returns a node standing for the component of the given node. Two nodes
+    // are in the same component exactly when they have the same
+    // representative, though it may change as components merge.
+    pub fn representative(&mut self, node: i64) -> Option<i64> {
+        let slot = *self.slot.get(&node)?;
+        let root = self.sets.find(slot);
+        Some(self.node_at[root])
+    }
+
+    pub fn connected(&mut self, a: i64, b: i64) -> bool {
+        match (self.slot.get(&a), self.slot.get(&b)) {
+            (Some(&a), Some(&b)) => self.sets.same_set(a, b),
+            _ => false,
+        }
+    }
+
+    // Number of weakly connected components
+    pub fn count(&self) -> usize {
+        self.sets.set_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use io;
+
+    // {0, 1, 2} -> {3, 4} -> {5}, plus a self-loop on 5 and a lone node 6
+    fn three_cycles() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..7).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (1, 4), (4, 5), (5, 5)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    fn normalized(mut components: Vec<Vec<i64>>) -> Vec<Vec<i64>> {
+        for c in &mut components {
+            c.sort();
+        }
+        components.sort();
+        components
+    }
+
+    #[test]
+    fn tarjan_and_kosaraju_agree() {
+        let (g, n) = three_cycles();
+        let expected = vec![vec![n[0], n[1], n[2]], vec![n[3], n[4]], vec![n[5]], vec![n[6]]];
+        assert_eq!(normalized(tarjan_scc(&g)), expected);
+        assert_eq!(normalized(kosaraju_scc(&g)), expected);
+
+        // Sinks first for Tarjan, sources first for Kosaraju
+        let tarjan = tarjan_scc(&g);
+        let pos = |c: &Vec<Vec<i64>>, v: i64| c.iter().position(|m| m.contains(&v)).unwrap();
+        assert!(pos(&tarjan, n[5]) < pos(&tarjan, n[3]) && pos(&tarjan, n[3]) < pos(&tarjan, n[0]));
+        let kosaraju = kosaraju_scc(&g);
+        assert!(pos(&kosaraju, n[0]) < pos(&kosaraju, n[3]) && pos(&kosaraju, n[3]) < pos(&kosaraju, n[5]));
+    }
+
+    #[test]
+    fn long_cycle_does_not_recurse() {
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let n: Vec<i64> = (0..100_000).map(|_| g.add_node(())).collect();
+        for w in n.windows(2) {
+            g.add_edge(w[0], w[1], ());
+        }
+        assert_eq!(tarjan_scc(&g).len(), n.len());
+        g.add_edge(n[n.len() - 1], n[0], ());
+        assert_eq!(tarjan_scc(&g).len(), 1);
+        assert_eq!(kosaraju_scc(&g).len(), 1);
+    }
+
+    #[test]
+    fn condensation_is_a_dag() {
+        let (g, n) = three_cycles();
+        let dag = condensation(&g);
+        assert_eq!(dag.order(), 4);
+        assert_eq!(dag.size(), 2);
+        for edge in dag.edges() {
+            let (start, end) = dag.get_nodes_of(edge).unwrap();
+            assert!(start < end);
+        }
+        let id = |v: i64| dag.nodes().find(|&c| dag.get_node_data(c).unwrap().contains(&v)).unwrap();
+        let between = dag.get_edge(id(n[0]), id(n[3])).unwrap();
+        assert_eq!(dag.get_edge_data(between), Some(&2));
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        let dag = condensation(&twitter);
+        assert_eq!(dag.order(), twitter.order());
+        assert_eq!(dag.size(), twitter.size());
+    }
+
+    #[test]
+    fn weak_components() {
+        let (g, n) = three_cycles();
+        assert_eq!(normalized(weakly_connected_components(&g)),
+                   vec![vec![n[0], n[1], n[2], n[3], n[4], n[5]], vec![n[6]]]);
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        assert_eq!(weakly_connected_components(&twitter).len(), 1);
+    }
+
+    #[test]
+    fn incremental_components_track_add_edge() {
+        let (g, n) = three_cycles();
+        let mut tracked = IncrementalComponents::from_graph(g);
+        assert_eq!(tracked.count(), 2);
+        assert!(tracked.connected(n[0], n[5]));
+        assert!(!tracked.connected(n[0], n[6]));
+
+        let extra = tracked.add_node(());
+        assert_eq!(tracked.count(), 3);
+        tracked.add_edge(extra, n[6], ());
+        assert_eq!(tracked.count(), 2);
+        assert_eq!(tracked.representative(extra), tracked.representative(n[6]));
+        tracked.add_edge(n[6], n[2], ());
+        assert_eq!(tracked.count(), 1);
+        assert!(tracked.connected(extra, n[0]));
+        assert_eq!(tracked.add_edge(extra, 1000, ()), None);
+
+        let g = tracked.into_graph();
+        assert_eq!(weakly_connected_components(&g).len(), 1);
+
+        // Default needs no Default data, just like new().
+        struct Opaque;
+        let mut empty: IncrementalComponents<Opaque, Opaque> = Default::default();
+        let a = empty.add_node(Opaque);
+        assert_eq!(empty.count(), 1);
+        assert_eq!(empty.representative(a), Some(a));
+    }
+}
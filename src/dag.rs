@@ -0,0 +1,298 @@
+use graph::Graph;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use weight::Weight;
+
+/**
+ * Error returned when a graph that should be acyclic is not. The cycle is
+ * listed in edge direction, starting at an arbitrary node of it and without
+ * repeating that node at the end.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<TIndex> {
+    nodes: Vec<TIndex>,
+}
+
+impl<TIndex> Cycle<TIndex> {
+    pub fn nodes(&self) -> &[TIndex] {
+        &self.nodes
+    }
+
+    pub fn into_nodes(self) -> Vec<TIndex> {
+        self.nodes
+    }
+}
+
+impl<TIndex: fmt::Debug> fmt::Display for Cycle<TIndex> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "graph contains a cycle through {:?}", self.nodes)
+    }
+}
+
+impl<TIndex: fmt::Debug> error::Error for Cycle<TIndex> {}
+
+// The set of nodes whose predecessors have all been emitted. Its pop order
+// decides which of several valid topological orders Kahn's algorithm yields.
+trait Frontier<T> {
+    fn push(&mut self, node: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+impl<T> Frontier<T> for VecDeque<T> {
+    fn push(&mut self, node: T) {
+        self.push_back(node);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+impl<T: Ord> Frontier<T> for BinaryHeap<Reverse<T>> {
+    fn push(&mut self, node: T) {
+        BinaryHeap::push(self, Reverse(node));
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self).map(|Reverse(node)| node)
+    }
+}
+
+fn kahn<N, E, G, Q>(graph: &G, mut frontier: Q) -> Result<Vec<G::TIndex>, Cycle<G::TIndex>>
+    where G: Graph<N, E>, Q: Frontier<G::TIndex>
+{
+    let mut indegree: HashMap<G::TIndex, usize> = HashMap::with_capacity(graph.order());
+    for node in graph.nodes() {
+        let degree = graph.get_indegree_of(node).unwrap_or(0);
+        if degree == 0 {
+            frontier.push(node);
+        }
+        indegree.insert(node, degree);
+    }
+
+    let mut order = Vec::with_capacity(graph.order());
+    while let Some(node) = frontier.pop() {
+        order.push(node);
+        for next in graph.get_succ_nodes_of(node) {
+            if let Some(degree) = indegree.get_mut(&next) {
+                *degree -= 1;
+                if *degree == 0 {
+                    frontier.push(next);
+                }
+            }
+        }
+    }
+
+    if order.len() == indegree.len() {
+        Ok(order)
+    } else {
+        Err(find_cycle(graph, &indegree))
+    }
+}
+
+// Every node Kahn's algorithm could not emit still has a predecessor that was
+// not emitted either, so walking predecessors from any of them must come back
+// round to a node already seen.
+fn find_cycle<N, E, G>(graph: &G, indegree: &HashMap<G::TIndex, usize>) -> Cycle<G::TIndex>
+    where G: Graph<N, E>
+{
+    let stuck = |node: &G::TIndex| indegree.get(node).is_some_and(|&d| d > 0);
+    let mut node = *indegree.keys().find(|n| stuck(n)).expect("no node left with predecessors");
+    let mut seen = HashMap::new();
+    let mut walk = Vec::new();
+    while !seen.contains_key(&node) {
+        seen.insert(node, walk.len());
+        walk.push(node);
+        node = graph.get_pred_nodes_of(node)
+            .find(stuck)
+            .expect("stuck node without stuck predecessor");
+    }
+    let mut nodes = walk.split_off(seen[&node]);
+    nodes.reverse();
+    Cycle { nodes }
+}
+
+// Orders the nodes so that every edge runs from an earlier node to a later
+// one, using Kahn's algorithm. Fails with one of the cycles if there is none.
+pub fn toposort<N, E, G>(graph: &G) -> Result<Vec<G::TIndex>, Cycle<G::TIndex>>
+    where G: Graph<N, E>
+{
+    kahn(graph, VecDeque::new())
+}
+
+// Like toposort, but whenever several nodes could come next, picks the one
+// with the smallest index. The result is the lexicographically smallest
+// topological order, and the same for every run on the same graph.
+pub fn lexicographic_toposort<N, E, G>(graph: &G) -> Result<Vec<G::TIndex>, Cycle<G::TIndex>>
+    where G: Graph<N, E>, G::TIndex: Ord
+{
+    kahn(graph, BinaryHeap::new())
+}
+
+// A path's total weight and its nodes in order
+pub type WeightedPath<TIndex, W> = (W, Vec<TIndex>);
+
+// Finds the heaviest path in a DAG, e.g. the critical path through a job
+// graph whose edges carry durations. Returns its total weight and its nodes;
+// an empty graph yields an empty path of weight zero.
+pub fn longest_path<N, E, G, W, F>(graph: &G, mut weight: F) -> Result<WeightedPath<G::TIndex, W>, Cycle<G::TIndex>>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let order = toposort(graph)?;
+    let mut dist: HashMap<G::TIndex, W> = order.iter().map(|&n| (n, W::zero())).collect();
+    let mut pred: HashMap<G::TIndex, G::TIndex> = HashMap::new();
+    let mut best: Option<(W, G::TIndex)> = None;
+
+    for &node in &order {
+        let here = dist[&node];
+        if best.is_none_or(|(w, _)| here > w) {
+            best = Some((here, node));
+        }
+        for edge in graph.get_succ_edges_of(node) {
+            let (next, data) = match (graph.get_edge_end(edge), graph.get_edge_data(edge)) {
+                (Some(next), Some(data)) => (next, data),
+                _ => continue,
+            };
+            let through = here + weight(data);
+            if through > dist[&next] {
+                dist.insert(next, through);
+                pred.insert(next, node);
+            }
+        }
+    }
+
+    let (total, end) = match best {
+        Some(best) => best,
+        None => return Ok((W::zero(), Vec::new())),
+    };
+    let mut path = vec![end];
+    while let Some(&prev) = pred.get(&path[path.len() - 1]) {
+        path.push(prev);
+    }
+    path.reverse();
+    Ok((total, path))
+}
+
+// Returns the edges of the transitive reduction of a DAG: the fewest edges
+// that still connect every pair of nodes the full graph connects. Of several
+// parallel edges only one is kept. Takes O(order * size) time.
+pub fn transitive_reduction<N, E, G>(graph: &G) -> Result<Vec<G::TIndex>, Cycle<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let order = toposort(graph)?;
+    let position: HashMap<G::TIndex, usize> = order.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+
+    let mut kept = Vec::new();
+    let mut reachable = HashSet::new();
+    let mut stack = Vec::new();
+    for &node in &order {
+        // A successor reachable through an earlier successor makes the
+        // direct edge to it redundant. Earlier in topological order means
+        // those successors are handled first.
+        let mut succ: Vec<(usize, G::TIndex, G::TIndex)> = graph.get_succ_edges_of(node)
+            .filter_map(|e| graph.get_edge_end(e).map(|n| (position[&n], n, e)))
+            .collect();
+        succ.sort_by_key(|&(pos, _, _)| pos);
+
+        reachable.clear();
+        for (_, next, edge) in succ {
+            if !reachable.insert(next) {
+                continue;
+            }
+            kept.push(edge);
+            mark_descendants(graph, next, &mut reachable, &mut stack);
+        }
+    }
+    Ok(kept)
+}
+
+fn mark_descendants<N, E, G, T>(graph: &G, from: T, reachable: &mut HashSet<T>, stack: &mut Vec<T>)
+    where G: Graph<N, E, TIndex = T>, T: Copy + Eq + Hash
+{
+    stack.push(from);
+    while let Some(node) = stack.pop() {
+        for next in graph.get_succ_nodes_of(node) {
+            if reachable.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    // Jobs 0..6 with durations on the dependency edges
+    //   0 -3-> 1 -2-> 3 -4-> 5
+    //   0 -1-> 2 -5-> 3
+    //   2 -1-> 4 -1-> 5
+    //   0 -9-> 5
+    fn jobs() -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b, w) in &[(0, 1, 3), (1, 3, 2), (3, 5, 4), (0, 2, 1), (2, 3, 5),
+                            (2, 4, 1), (4, 5, 1), (0, 5, 9)] {
+            g.add_edge(n[a], n[b], w);
+        }
+        (g, n)
+    }
+
+    fn respects_edges(g: &AdjListGraph<(), i32>, order: &[i64]) -> bool {
+        let pos: HashMap<i64, usize> = order.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        order.len() == g.order() && g.edges().all(|e| {
+            let (a, b) = g.get_nodes_of(e).unwrap();
+            pos[&a] < pos[&b]
+        })
+    }
+
+    #[test]
+    fn orders_and_cycles() {
+        let (mut g, n) = jobs();
+        assert!(respects_edges(&g, &toposort(&g).unwrap()));
+        assert_eq!(lexicographic_toposort(&g).unwrap(), vec![n[0], n[1], n[2], n[3], n[4], n[5]]);
+
+        g.add_edge(n[5], n[2], 0);
+        let cycle = toposort(&g).unwrap_err().into_nodes();
+        assert_eq!(cycle.len(), 3);
+        for (i, &a) in cycle.iter().enumerate() {
+            assert!(g.has_edge_between(a, cycle[(i + 1) % cycle.len()]));
+        }
+        assert!(lexicographic_toposort(&g).is_err());
+    }
+
+    #[test]
+    fn lexicographic_order_prefers_small_indices() {
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let n: Vec<i64> = (0..4).map(|_| g.add_node(())).collect();
+        g.add_edge(n[3], n[0], ());
+        g.add_edge(n[2], n[1], ());
+        assert_eq!(lexicographic_toposort(&g).unwrap(), vec![n[2], n[1], n[3], n[0]]);
+    }
+
+    #[test]
+    fn critical_path() {
+        let (g, n) = jobs();
+        assert_eq!(longest_path(&g, |&w| w).unwrap(), (10, vec![n[0], n[2], n[3], n[5]]));
+
+        let empty: AdjListGraph<(), i32> = AdjListGraph::new();
+        assert_eq!(longest_path(&empty, |&w| w).unwrap(), (0, vec![]));
+    }
+
+    #[test]
+    fn reduction_drops_implied_edges() {
+        let (mut g, n) = jobs();
+        g.add_edge(n[0], n[1], 7);
+        let mut kept: Vec<(i64, i64)> = transitive_reduction(&g).unwrap()
+            .into_iter()
+            .map(|e| g.get_nodes_of(e).unwrap())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, vec![(n[0], n[1]), (n[0], n[2]), (n[1], n[3]), (n[2], n[3]),
+                              (n[2], n[4]), (n[3], n[5]), (n[4], n[5])]);
+    }
+}
@@ -0,0 +1,327 @@
+use adjlistgraph::AdjListGraph;
+use graph::Graph;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use traversal::{neighbors, Dfs, Direction, Event};
+
+/**
+ * The dominator tree of the nodes reachable from a root. A node d dominates
+ * n when every path from the root to n passes through d; the immediate
+ * dominator of n is the strict dominator closest to it, and is its parent in
+ * the tree.
+ *
+ * For post-dominators the paths run against the edges, from every node to
+ * the exit, and the tree describes the reverse graph.
+ */
+#[derive(Debug, Clone)]
+pub struct Dominators<TIndex: Hash + Eq> {
+    root: TIndex,
+    direction: Direction,
+
+    // Whether the root is in the graph at all; if not, the tree is empty.
+    has_root: bool,
+
+    // Maps every reachable node other than the root to its immediate
+    // dominator.
+    idom: HashMap<TIndex, TIndex>,
+}
+
+impl<TIndex: Copy + Hash + Eq> Dominators<TIndex> {
+    pub fn root(&self) -> TIndex {
+        self.root
+    }
+
+    // Returns whether a node can be reached from the root, i.e. whether it
+    // appears in the tree.
+    pub fn is_reachable(&self, node: TIndex) -> bool {
+        (node == self.root && self.has_root) || self.idom.contains_key(&node)
+    }
+
+    // Returns the parent of a node in the dominator tree. The root and
+    // unreachable nodes have none.
+    pub fn immediate_dominator(&self, node: TIndex) -> Option<TIndex> {
+        self.idom.get(&node).cloned()
+    }
+
+    // Returns every dominator of a node, starting with the node itself and
+    // climbing the tree up to the root.
+    pub fn dominators_of(&self, node: TIndex) -> Option<Vec<TIndex>> {
+        if !self.is_reachable(node) {
+            return None;
+        }
+        let mut chain = vec![node];
+        let mut cur = node;
+        while let Some(&up) = self.idom.get(&cur) {
+            chain.push(up);
+            cur = up;
+        }
+        Some(chain)
+    }
+
+    // Returns whether a dominates b. Every reachable node dominates itself.
+    pub fn dominates(&self, a: TIndex, b: TIndex) -> bool {
+        if !self.is_reachable(a) || !self.is_reachable(b) {
+            return false;
+        }
+        let mut cur = b;
+        loop {
+            if cur == a {
+                return true;
+            }
+            match self.idom.get(&cur) {
+                Some(&up) => cur = up,
+                None => return false,
+            }
+        }
+    }
+
+    // Builds the dominator tree as a graph of its own, holding the original
+    // indices as node data, with an edge from each immediate dominator to
+    // the nodes it dominates.
+    pub fn tree(&self) -> AdjListGraph<TIndex, ()> {
+        let mut tree = AdjListGraph::with_parallel_edges(false);
+        if !self.has_root {
+            return tree;
+        }
+        let mut id = HashMap::with_capacity(self.idom.len() + 1);
+        id.insert(self.root, tree.add_node(self.root));
+        for &node in self.idom.keys() {
+            id.insert(node, tree.add_node(node));
+        }
+        for (node, up) in &self.idom {
+            tree.add_edge(id[up], id[node], ());
+        }
+        tree
+    }
+}
+
+// Cooper, Harvey and Kennedy's iterative algorithm. Nodes are numbered in
+// postorder of a DFS from the root and visited in reverse postorder, each
+// taking the nearest common ancestor of its already processed predecessors
+// as its dominator, until nothing changes. Typically converges in two or
+// three passes.
+fn compute<N, E, G>(graph: &G, root: G::TIndex, direction: Direction) -> Dominators<G::TIndex>
+    where G: Graph<N, E>
+{
+    let mut postorder = Vec::new();
+    let mut dfs = Dfs::new(graph, root, direction);
+    while let Some(event) = dfs.next_event() {
+        if let Event::Finish(node, _) = event {
+            postorder.push(node);
+        }
+    }
+    let number: HashMap<G::TIndex, usize> = postorder.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let preds: Vec<Vec<usize>> = postorder.iter()
+        .map(|&n| neighbors(graph, n, direction.reversed()).filter_map(|p| number.get(&p).cloned()).collect())
+        .collect();
+
+    // The root finishes last, so it holds the highest number and is its own
+    // dominator while the fixpoint is computed.
+    let top = postorder.len().saturating_sub(1);
+    let mut doms: Vec<Option<usize>> = vec![None; postorder.len()];
+    if let Some(root_dom) = doms.last_mut() {
+        *root_dom = Some(top);
+    }
+    let intersect = |doms: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while a < b {
+                a = doms[a].unwrap_or(top);
+            }
+            while b < a {
+                b = doms[b].unwrap_or(top);
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in (0..top).rev() {
+            let mut new_idom = None;
+            for &p in &preds[b] {
+                if doms[p].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => p,
+                    Some(cur) => intersect(&doms, p, cur),
+                });
+            }
+            if new_idom.is_some() && doms[b] != new_idom {
+                doms[b] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    let idom = (0..top)
+        .filter_map(|b| doms[b].map(|d| (postorder[b], postorder[d])))
+        .collect();
+    Dominators { root, direction, has_root: !postorder.is_empty(), idom }
+}
+
+// Computes the dominator tree of the nodes reachable from root along the
+// edges. Runs in O(size * passes) with a small number of passes on
+// reducible graphs such as structured control flow.
+pub fn dominators<N, E, G>(graph: &G, root: G::TIndex) -> Dominators<G::TIndex>
+    where G: Graph<N, E>
+{
+    compute(graph, root, Direction::Outgoing)
+}
+
+// Computes the post-dominator tree: the dominator tree of the reverse graph
+// rooted at exit, covering the nodes that can reach it. A graph with several
+// exits needs a single virtual exit node joined to all of them first.
+pub fn post_dominators<N, E, G>(graph: &G, exit: G::TIndex) -> Dominators<G::TIndex>
+    where G: Graph<N, E>
+{
+    compute(graph, exit, Direction::Incoming)
+}
+
+// Computes the dominance frontier of every node in the tree: the nodes just
+// beyond where its dominance ends, i.e. those with a predecessor it
+// dominates that it does not strictly dominate itself. Given post-dominators
+// it returns the post-dominance frontiers, which are the control dependences.
+pub fn dominance_frontiers<N, E, G>(graph: &G, doms: &Dominators<G::TIndex>)
+    -> HashMap<G::TIndex, HashSet<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let mut frontiers: HashMap<G::TIndex, HashSet<G::TIndex>> = HashMap::with_capacity(doms.idom.len() + 1);
+    if doms.has_root {
+        frontiers.insert(doms.root, HashSet::new());
+    }
+    for &node in doms.idom.keys() {
+        frontiers.insert(node, HashSet::new());
+    }
+
+    let members: Vec<G::TIndex> = frontiers.keys().cloned().collect();
+    for node in members {
+        let preds: Vec<G::TIndex> = neighbors(graph, node, doms.direction.reversed())
+            .filter(|&p| doms.is_reachable(p))
+            .collect();
+        if preds.len() < 2 && node != doms.root {
+            continue;
+        }
+        let stop = doms.immediate_dominator(node);
+        for p in preds {
+            // Climb from the predecessor until reaching the node's immediate
+            // dominator; for the root, which has none, climb past the top.
+            let mut runner = Some(p);
+            while runner != stop {
+                let cur = match runner {
+                    Some(cur) => cur,
+                    None => break,
+                };
+                if let Some(frontier) = frontiers.get_mut(&cur) {
+                    frontier.insert(node);
+                }
+                runner = doms.immediate_dominator(cur);
+            }
+        }
+    }
+    frontiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The control-flow graph from Cooper, Harvey and Kennedy's paper:
+    // 0 is the entry, 1 and 2 its branches, 3 to 5 an irreducible tangle.
+    // 6 is unreachable.
+    fn cfg() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..7).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (0, 2), (1, 3), (2, 4), (2, 5), (3, 4), (4, 3), (4, 5), (5, 4), (6, 3)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    // entry -> cond -> {then, else} -> join -> loop header -> {body, exit},
+    // with body looping back to the header.
+    fn diamond_loop() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..8).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (1, 2), (1, 3), (2, 4), (3, 4), (4, 5), (5, 6), (6, 5), (5, 7)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn immediate_dominators() {
+        let (g, n) = cfg();
+        let doms = dominators(&g, n[0]);
+        assert_eq!(doms.root(), n[0]);
+        assert_eq!(doms.immediate_dominator(n[0]), None);
+        for &node in &n[1..6] {
+            assert_eq!(doms.immediate_dominator(node), Some(n[0]));
+        }
+        assert!(!doms.is_reachable(n[6]));
+        assert_eq!(doms.dominators_of(n[6]), None);
+
+        let (g, n) = diamond_loop();
+        let doms = dominators(&g, n[0]);
+        assert_eq!(doms.immediate_dominator(n[4]), Some(n[1]));
+        assert_eq!(doms.dominators_of(n[7]), Some(vec![n[7], n[5], n[4], n[1], n[0]]));
+        assert!(doms.dominates(n[5], n[6]));
+        assert!(!doms.dominates(n[2], n[4]));
+
+        let tree = doms.tree();
+        assert_eq!(tree.order(), 8);
+        assert_eq!(tree.size(), 7);
+    }
+
+    #[test]
+    fn missing_root_reaches_nothing() {
+        let (g, n) = diamond_loop();
+        let missing = 1000;
+        for doms in &[dominators(&g, missing), post_dominators(&g, missing)] {
+            assert!(!doms.is_reachable(missing));
+            assert!(!doms.dominates(missing, missing));
+            assert_eq!(doms.dominators_of(missing), None);
+            assert_eq!(doms.immediate_dominator(n[1]), None);
+            assert_eq!(doms.tree().order(), 0);
+            assert!(dominance_frontiers(&g, doms).is_empty());
+        }
+    }
+
+    #[test]
+    fn post_dominators_reverse_the_edges() {
+        let (g, n) = diamond_loop();
+        let post = post_dominators(&g, n[7]);
+        assert_eq!(post.immediate_dominator(n[1]), Some(n[4]));
+        assert_eq!(post.immediate_dominator(n[2]), Some(n[4]));
+        assert_eq!(post.immediate_dominator(n[6]), Some(n[5]));
+        assert_eq!(post.dominators_of(n[0]), Some(vec![n[0], n[1], n[4], n[5], n[7]]));
+    }
+
+    #[test]
+    fn frontiers() {
+        let (g, n) = diamond_loop();
+        let df = dominance_frontiers(&g, &dominators(&g, n[0]));
+        let set = |nodes: &[i64]| nodes.iter().cloned().collect::<HashSet<i64>>();
+        assert_eq!(df[&n[2]], set(&[n[4]]));
+        assert_eq!(df[&n[3]], set(&[n[4]]));
+        assert_eq!(df[&n[6]], set(&[n[5]]));
+        assert_eq!(df[&n[5]], set(&[n[5]]));
+        assert_eq!(df[&n[1]], set(&[]));
+        assert_eq!(df[&n[7]], set(&[]));
+
+        // The branches are control dependent on the condition.
+        let cdg = dominance_frontiers(&g, &post_dominators(&g, n[7]));
+        assert_eq!(cdg[&n[2]], set(&[n[1]]));
+        assert_eq!(cdg[&n[3]], set(&[n[1]]));
+        assert_eq!(cdg[&n[6]], set(&[n[5]]));
+        assert_eq!(cdg[&n[4]], set(&[]));
+
+        let (g, n) = cfg();
+        let df = dominance_frontiers(&g, &dominators(&g, n[0]));
+        assert_eq!(df[&n[1]], set(&[n[3]]));
+        assert_eq!(df[&n[3]], set(&[n[4]]));
+        assert_eq!(df[&n[4]], set(&[n[3], n[5]]));
+        assert!(!df.contains_key(&n[6]));
+    }
+}
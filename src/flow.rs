@@ -0,0 +1,385 @@
+use graph::Graph;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use weight::Weight;
+
+// Edge payloads give each edge a capacity through a closure; capacities must
+// not be negative. Flow runs along edges in their direction, and parallel or
+// opposite edges are separate arcs with their own capacities.
+
+// A maximum flow: its value and the flow on every edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaxFlow<TIndex: Hash + Eq, W> {
+    pub value: W,
+    pub flow: HashMap<TIndex, W>,
+}
+
+// A minimum s-t cut: the nodes still reachable from the source in the
+// residual graph of a maximum flow, the rest, and the edges leading from the
+// first set to the second. Their capacities add up to the maximum flow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinCut<TIndex: Hash + Eq, W> {
+    pub value: W,
+    pub source_side: HashSet<TIndex>,
+    pub sink_side: HashSet<TIndex>,
+    pub edges: Vec<TIndex>,
+}
+
+// A residual arc: an edge, and whether it is used forwards (pushing more
+// flow) or backwards (cancelling flow already on it).
+type Arc = (usize, bool);
+
+// The flow network renumbered to 0..n. A node's residual arcs are its
+// outgoing edges used forwards and its incoming edges used backwards, which
+// is how the succ and pred lists of the graph map onto the residual graph
+// without adding reverse edges to it.
+struct Network<T, W> {
+    nodes: Vec<T>,
+    slot: HashMap<T, usize>,
+    edges: Vec<T>,
+    from: Vec<usize>,
+    to: Vec<usize>,
+    capacity: Vec<W>,
+    flow: Vec<W>,
+    arcs: Vec<Vec<Arc>>,
+}
+
+impl<T: Copy + Hash + Eq, W: Weight> Network<T, W> {
+    fn new<N, E, G, F>(graph: &G, mut capacity: F) -> Network<T, W>
+        where G: Graph<N, E, TIndex = T>, F: FnMut(&E) -> W
+    {
+        let nodes: Vec<T> = graph.nodes().collect();
+        let slot: HashMap<T, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut network = Network {
+            arcs: vec![Vec::new(); nodes.len()],
+            nodes,
+            slot,
+            edges: Vec::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+            capacity: Vec::new(),
+            flow: Vec::new(),
+        };
+        for edge in graph.edges() {
+            let (start, end) = match graph.get_nodes_of(edge) {
+                Some((start, end)) => (network.slot[&start], network.slot[&end]),
+                None => continue,
+            };
+            let cap = match graph.get_edge_data(edge) {
+                Some(data) => capacity(data),
+                None => continue,
+            };
+            let k = network.edges.len();
+            network.edges.push(edge);
+            network.from.push(start);
+            network.to.push(end);
+            network.capacity.push(cap);
+            network.flow.push(W::zero());
+            if start != end {
+                network.arcs[start].push((k, true));
+                network.arcs[end].push((k, false));
+            }
+        }
+        network
+    }
+
+    fn residual(&self, (k, forward): Arc) -> W {
+        if forward {
+            self.capacity[k] - self.flow[k]
+        } else {
+            self.flow[k]
+        }
+    }
+
+    fn head(&self, (k, forward): Arc) -> usize {
+        if forward { self.to[k] } else { self.from[k] }
+    }
+
+    fn push(&mut self, (k, forward): Arc, amount: W) {
+        if forward {
+            self.flow[k] = self.flow[k] + amount;
+        } else {
+            self.flow[k] = self.flow[k] - amount;
+        }
+    }
+
+    // Breadth-first distances from s over arcs with residual capacity left
+    fn levels(&self, s: usize) -> Vec<Option<usize>> {
+        let mut level = vec![None; self.nodes.len()];
+        let mut queue = VecDeque::new();
+        level[s] = Some(0);
+        queue.push_back(s);
+        while let Some(u) = queue.pop_front() {
+            for &arc in &self.arcs[u] {
+                let v = self.head(arc);
+                if level[v].is_none() && self.residual(arc) > W::zero() {
+                    level[v] = level[u].map(|l| l + 1);
+                    queue.push_back(v);
+                }
+            }
+        }
+        level
+    }
+
+    fn into_max_flow(self, value: W) -> MaxFlow<T, W> {
+        MaxFlow {
+            value,
+            flow: self.edges.into_iter().zip(self.flow).collect(),
+        }
+    }
+}
+
+// Finds augmenting paths along the level graph until it has none left, each
+// time advancing from the source and backing out of dead ends. Returns the
+// flow added.
+fn blocking_flow<T, W>(net: &mut Network<T, W>, s: usize, t: usize, level: &mut [Option<usize>]) -> W
+    where T: Copy + Hash + Eq, W: Weight
+{
+    let mut next_arc = vec![0; net.nodes.len()];
+    let mut path: Vec<Arc> = Vec::new();
+    let mut total = W::zero();
+    let mut u = s;
+    loop {
+        if u == t {
+            let bottleneck = path.iter().map(|&arc| net.residual(arc)).min().unwrap_or(W::zero());
+            for &arc in &path {
+                net.push(arc, bottleneck);
+            }
+            total = total + bottleneck;
+            path.clear();
+            u = s;
+            continue;
+        }
+
+        let mut advanced = false;
+        while next_arc[u] < net.arcs[u].len() {
+            let arc = net.arcs[u][next_arc[u]];
+            let v = net.head(arc);
+            if net.residual(arc) > W::zero() && level[v].is_some() && level[v] == level[u].map(|l| l + 1) {
+                path.push(arc);
+                u = v;
+                advanced = true;
+                break;
+            }
+            next_arc[u] += 1;
+        }
+        if advanced {
+            continue;
+        }
+
+        // Dead end: nothing useful is reachable from u any more.
+        level[u] = None;
+        match path.pop() {
+            Some(arc) => {
+                u = net.head((arc.0, !arc.1));
+                next_arc[u] += 1;
+            }
+            None => return total,
+        }
+    }
+}
+
+// Computes a maximum flow from source to sink with Dinic's algorithm, which
+// saturates shortest augmenting paths one distance at a time. Runs in
+// O(order^2 * size). Missing nodes or source == sink give a zero flow.
+pub fn dinic<N, E, G, W, F>(graph: &G, source: G::TIndex, sink: G::TIndex, capacity: F) -> MaxFlow<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut net = Network::new(graph, capacity);
+    let value = run_dinic(&mut net, source, sink);
+    net.into_max_flow(value)
+}
+
+fn run_dinic<T, W>(net: &mut Network<T, W>, source: T, sink: T) -> W
+    where T: Copy + Hash + Eq, W: Weight
+{
+    let (s, t) = match (net.slot.get(&source), net.slot.get(&sink)) {
+        (Some(&s), Some(&t)) if s != t => (s, t),
+        _ => return W::zero(),
+    };
+    let mut value = W::zero();
+    loop {
+        let mut level = net.levels(s);
+        if level[t].is_none() {
+            return value;
+        }
+        value = value + blocking_flow(net, s, t, &mut level);
+    }
+}
+
+// Computes a maximum flow with the FIFO push-relabel algorithm, which floods
+// excess flow downhill from the source and raises nodes that cannot get rid
+// of theirs. Runs in O(order^3) and tends to beat Dinic on dense graphs.
+pub fn push_relabel<N, E, G, W, F>(graph: &G, source: G::TIndex, sink: G::TIndex, capacity: F)
+    -> MaxFlow<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut net = Network::new(graph, capacity);
+    let (s, t) = match (net.slot.get(&source), net.slot.get(&sink)) {
+        (Some(&s), Some(&t)) if s != t => (s, t),
+        _ => return net.into_max_flow(W::zero()),
+    };
+
+    let n = net.nodes.len();
+    let mut height = vec![0; n];
+    let mut excess = vec![W::zero(); n];
+    let mut next_arc = vec![0; n];
+    let mut active = VecDeque::new();
+
+    height[s] = n;
+    for i in 0..net.arcs[s].len() {
+        let arc = net.arcs[s][i];
+        let amount = net.residual(arc);
+        if amount > W::zero() {
+            let v = net.head(arc);
+            net.push(arc, amount);
+            if v != t && excess[v] == W::zero() {
+                active.push_back(v);
+            }
+            excess[v] = excess[v] + amount;
+        }
+    }
+
+    while let Some(u) = active.pop_front() {
+        while excess[u] > W::zero() {
+            if next_arc[u] == net.arcs[u].len() {
+                // Relabel: rise just above the lowest neighbor that can
+                // still take flow. The arc the excess came in on always can.
+                height[u] = 1 + net.arcs[u].iter()
+                    .filter(|&&arc| net.residual(arc) > W::zero())
+                    .map(|&arc| height[net.head(arc)])
+                    .min()
+                    .unwrap_or(2 * n);
+                next_arc[u] = 0;
+                continue;
+            }
+            let arc = net.arcs[u][next_arc[u]];
+            let v = net.head(arc);
+            let room = net.residual(arc);
+            if room > W::zero() && height[u] == height[v] + 1 {
+                let amount = if excess[u] < room { excess[u] } else { room };
+                net.push(arc, amount);
+                excess[u] = excess[u] - amount;
+                if v != s && v != t && excess[v] == W::zero() {
+                    active.push_back(v);
+                }
+                excess[v] = excess[v] + amount;
+            } else {
+                next_arc[u] += 1;
+            }
+        }
+    }
+
+    let value = excess[t];
+    net.into_max_flow(value)
+}
+
+// Computes a minimum s-t cut, i.e. the cheapest set of edges whose removal
+// leaves no path from source to sink, from a maximum flow found with Dinic's
+// algorithm.
+pub fn min_cut<N, E, G, W, F>(graph: &G, source: G::TIndex, sink: G::TIndex, capacity: F) -> MinCut<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut net = Network::new(graph, capacity);
+    let value = run_dinic(&mut net, source, sink);
+    let reached = match net.slot.get(&source) {
+        Some(&s) => net.levels(s),
+        None => vec![None; net.nodes.len()],
+    };
+
+    let mut cut = MinCut {
+        value,
+        source_side: HashSet::new(),
+        sink_side: HashSet::new(),
+        edges: Vec::new(),
+    };
+    for (i, &node) in net.nodes.iter().enumerate() {
+        if reached[i].is_some() {
+            cut.source_side.insert(node);
+        } else {
+            cut.sink_side.insert(node);
+        }
+    }
+    for k in 0..net.edges.len() {
+        if reached[net.from[k]].is_some() && reached[net.to[k]].is_none() {
+            cut.edges.push(net.edges[k]);
+        }
+    }
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    // The textbook network from Cormen et al. with a maximum flow of 23:
+    // s = 0, t = 5.
+    fn clrs() -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b, c) in &[(0, 1, 16), (0, 2, 13), (2, 1, 4), (1, 3, 12), (3, 2, 9),
+                            (2, 4, 14), (4, 3, 7), (3, 5, 20), (4, 5, 4)] {
+            g.add_edge(n[a], n[b], c);
+        }
+        (g, n)
+    }
+
+    // Checks capacity limits and conservation of flow at inner nodes.
+    fn is_valid(g: &AdjListGraph<(), i32>, flow: &MaxFlow<i64, i32>, s: i64, t: i64) -> bool {
+        let within = g.edges().all(|e| 0 <= flow.flow[&e] && flow.flow[&e] <= *g.get_edge_data(e).unwrap());
+        let conserved = g.nodes().filter(|&v| v != s && v != t).all(|v| {
+            let inflow: i32 = g.get_pred_edges_of(v).map(|e| flow.flow[&e]).sum();
+            let outflow: i32 = g.get_succ_edges_of(v).map(|e| flow.flow[&e]).sum();
+            inflow == outflow
+        });
+        let out_of_source: i32 = g.get_succ_edges_of(s).map(|e| flow.flow[&e]).sum::<i32>()
+            - g.get_pred_edges_of(s).map(|e| flow.flow[&e]).sum::<i32>();
+        within && conserved && out_of_source == flow.value
+    }
+
+    #[test]
+    fn dinic_and_push_relabel_agree() {
+        let (g, n) = clrs();
+        let d = dinic(&g, n[0], n[5], |&c| c);
+        let p = push_relabel(&g, n[0], n[5], |&c| c);
+        assert_eq!(d.value, 23);
+        assert_eq!(p.value, 23);
+        assert!(is_valid(&g, &d, n[0], n[5]));
+        assert!(is_valid(&g, &p, n[0], n[5]));
+
+        // Flow can run against an edge in the residual graph only by
+        // cancelling flow already on it.
+        assert_eq!(dinic(&g, n[5], n[0], |&c| c).value, 0);
+        assert_eq!(push_relabel(&g, n[2], n[2], |&c| c).value, 0);
+    }
+
+    #[test]
+    fn parallel_and_opposite_edges() {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..4).map(|_| g.add_node(())).collect();
+        for &(a, b, c) in &[(0, 1, 3), (0, 1, 2), (1, 2, 4), (2, 1, 10), (2, 3, 10), (1, 3, 1), (3, 3, 5)] {
+            g.add_edge(n[a], n[b], c);
+        }
+        let d = dinic(&g, n[0], n[3], |&c| c);
+        let p = push_relabel(&g, n[0], n[3], |&c| c);
+        assert_eq!((d.value, p.value), (5, 5));
+        assert!(is_valid(&g, &d, n[0], n[3]));
+        assert!(is_valid(&g, &p, n[0], n[3]));
+    }
+
+    #[test]
+    fn minimum_cut() {
+        let (g, n) = clrs();
+        let cut = min_cut(&g, n[0], n[5], |&c| c);
+        assert_eq!(cut.value, 23);
+        let capacity: i32 = cut.edges.iter().map(|&e| *g.get_edge_data(e).unwrap()).sum();
+        assert_eq!(capacity, 23);
+        assert_eq!(cut.source_side, [n[0], n[1], n[2], n[4]].iter().cloned().collect());
+        assert_eq!(cut.sink_side, [n[3], n[5]].iter().cloned().collect());
+
+        let mut ends: Vec<(i64, i64)> = cut.edges.iter().map(|&e| g.get_nodes_of(e).unwrap()).collect();
+        ends.sort();
+        assert_eq!(ends, vec![(n[1], n[3]), (n[4], n[3]), (n[4], n[5])]);
+    }
+}
@@ -1,22 +1,94 @@
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+
+// Reasons a graph mutation can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphError {
+    // A node index does not refer to a node in the graph
+    MissingNode,
+
+    // An edge index, or a pair of nodes, does not refer to an edge in the graph
+    MissingEdge,
+
+    // The graph does not allow parallel edges and the edge already exists
+    DuplicateEdge,
+
+    // The graph has run out of indices to hand out
+    CapacityExceeded,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match *self {
+            GraphError::MissingNode => "node is not in the graph",
+            GraphError::MissingEdge => "edge is not in the graph",
+            GraphError::DuplicateEdge => "edge already exists and parallel edges are disallowed",
+            GraphError::CapacityExceeded => "graph has run out of indices",
+        };
+        f.write_str(message)
+    }
+}
+
+impl error::Error for GraphError {}
+
+// Node and edge indices are small Copy handles, so accessors and iterators
+// hand them out by value. This lets backends that keep their adjacency lists
+// behind a RefCell (see RcGraph) implement the trait without leaking borrows.
+// Indices never borrow from the graph, which lets generic code box iterators
+// over them for any lifetime.
 pub trait Graph<TNode, TEdge> {
-    type TIndex: Copy;
+    type TIndex: Copy + Eq + Hash + 'static;
 
     // MUTATORS
+    //
+    // Backends implement the try_* variants, which report why a mutation
+    // failed. The plain variants are shorthands that discard the reason.
 
     // Adds a single node to the graph and returns its corresponding index.
-    fn add_node(&mut self, data: TNode) -> Self::TIndex;
+    fn try_add_node(&mut self, data: TNode) -> Result<Self::TIndex, GraphError>;
 
-    // Remove node n from the graph and returns its associated value.
-    fn remove_node(&mut self, node: Self::TIndex) -> Option<TNode>;
+    // Remove node n (and all incident edges) from the graph and returns its
+    // associated value.
+    fn try_remove_node(&mut self, node: Self::TIndex) -> Result<TNode, GraphError>;
 
     // Adds a single edge to the graph and returns its corresponding index.
-    fn add_edge(&mut self, node_a: Self::TIndex, node_b: Self::TIndex, data: TEdge) -> Option<Self::TIndex>;
+    fn try_add_edge(&mut self, node_a: Self::TIndex, node_b: Self::TIndex, data: TEdge) -> Result<Self::TIndex, GraphError>;
+
+    // Removes an edge and returns its associated value.
+    fn try_remove_edge(&mut self, edge: Self::TIndex) -> Result<TEdge, GraphError>;
 
     // Removes an edge between nodes a and b and returns its associated value.
-    fn remove_edge(&mut self, edge: Self::TIndex) -> Option<TEdge>;
+    fn try_remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Result<TEdge, GraphError>;
+
+    // Adds a single node to the graph and returns its corresponding index.
+    // Panics if the graph has run out of indices.
+    fn add_node(&mut self, data: TNode) -> Self::TIndex {
+        match self.try_add_node(data) {
+            Ok(idx) => idx,
+            Err(err) => panic!("add_node failed: {}", err),
+        }
+    }
+
+    // Remove node n from the graph and returns its associated value.
+    fn remove_node(&mut self, node: Self::TIndex) -> Option<TNode> {
+        self.try_remove_node(node).ok()
+    }
+
+    // Adds a single edge to the graph and returns its corresponding index.
+    fn add_edge(&mut self, node_a: Self::TIndex, node_b: Self::TIndex, data: TEdge) -> Option<Self::TIndex> {
+        self.try_add_edge(node_a, node_b, data).ok()
+    }
+
+    // Removes an edge and returns its associated value.
+    fn remove_edge(&mut self, edge: Self::TIndex) -> Option<TEdge> {
+        self.try_remove_edge(edge).ok()
+    }
 
     // Removes an edge between nodes a and b and returns its associated value.
-    fn remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<TEdge>;
+    fn remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<TEdge> {
+        self.try_remove_edge_between(node_a, node_b).ok()
+    }
 
     // Removes all nodes and edges from the graph.
     fn clear(&mut self);
@@ -42,41 +114,68 @@ pub trait Graph<TNode, TEdge> {
     fn has_edge_between(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> bool;
 
     // Returns the edge between two nodes.
-    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<&Self::TIndex>;
+    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<Self::TIndex>;
 
     // Returns a tuple containing the nodes that an edge connects.
-    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(&Self::TIndex, &Self::TIndex)>;
+    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(Self::TIndex, Self::TIndex)>;
 
     // Returns the degree (i.e. number of edges) of a node.
     fn get_degree_of(&self, node: Self::TIndex) -> Option<usize>;
 
-    // // Returns the start node of an edge.
-    // fn get_edge_start(&self, edge: Self::TIndex) -> Option<&Self::TIndex>;
+    // Returns the indegree (i.e. number of incoming edges) of a node.
+    fn get_indegree_of(&self, node: Self::TIndex) -> Option<usize>;
+
+    // Returns the outdegree (i.e. number of outgoing edges) of a node.
+    fn get_outdegree_of(&self, node: Self::TIndex) -> Option<usize>;
 
-    // // Returns the end node of an edge.
-    // fn get_edge_end(&self, edge: Self::TIndex) -> Option<&Self::TIndex>;
+    // Returns the start node of an edge.
+    fn get_edge_start(&self, edge: Self::TIndex) -> Option<Self::TIndex>;
+
+    // Returns the end node of an edge.
+    fn get_edge_end(&self, edge: Self::TIndex) -> Option<Self::TIndex>;
 
     // Returns the associated metadata with an edge between two nodes a and b.
     fn get_edge_data(&self, edge: Self::TIndex) -> Option<&TEdge>;
 
+    // Returns mutable access to the data contained in a node.
+    fn get_node_data_mut(&mut self, node: Self::TIndex) -> Option<&mut TNode>;
+
+    // Returns mutable access to the data contained in an edge.
+    fn get_edge_data_mut(&mut self, edge: Self::TIndex) -> Option<&mut TEdge>;
+
     // ITERATORS
 
     // Returns an iterator over all the graph nodes.
-    fn nodes<'a>(&'a self) -> Box<Iterator<Item = &'a Self::TIndex> + 'a>;
+    fn nodes<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a>;
 
     // Returns an iterator over all the graph edges.
-    fn edges<'a>(&'a self) -> Box<Iterator<Item = &'a Self::TIndex> + 'a>;
+    fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a>;
+
+    // Returns an iterator over all nodes along with mutable access to their data.
+    fn nodes_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TNode)> + 'a>;
+
+    // Returns an iterator over all edges along with mutable access to their data.
+    fn edges_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TEdge)> + 'a>;
 
     // Returns an iterator over all edges incident on a node.
     fn get_edges_of<'a>(&'a self, node: Self::TIndex) ->
-                            Box<Iterator<Item = &'a Self::TIndex> + 'a>;
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a>;
+
+    // Returns an iterator over all incoming edges of a node.
+    fn get_pred_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a>;
 
-    // // Returns an iterator over all incoming edges of a node.
-    // fn get_pred_edges_of<'a>(&'a self, node: Self::TIndex) ->
-    //                         Box<Iterator<Item = &'a Self::TIndex> + 'a>;
+    // Returns an iterator over all outgoing edges of a node.
+    fn get_succ_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a>;
 
-    // // Returns an iterator over all outgoing edges of a node.
-    // fn get_succ_edges_of<'a>(&'a self, node: Self::TIndex) ->
-    //                         Box<Iterator<Item = &'a Self::TIndex> + 'a>;
+    // Returns an iterator over the predecessors of a node, i.e. the start
+    // nodes of its incoming edges. A node appears once per parallel edge.
+    fn get_pred_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a>;
 
+    // Returns an iterator over the successors of a node, i.e. the end
+    // nodes of its outgoing edges. A node appears once per parallel edge.
+    fn get_succ_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a>;
 }
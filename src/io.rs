@@ -0,0 +1,183 @@
+use graph::{Graph, GraphError};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum EdgeListError {
+    // The underlying reader failed
+    Io(io::Error),
+
+    // A line could not be parsed; lines are numbered from 1
+    Parse { line: usize, message: String },
+
+    // The graph rejected a line's node or edge
+    Graph { line: usize, err: GraphError },
+}
+
+impl fmt::Display for EdgeListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EdgeListError::Io(ref err) => write!(f, "read error: {}", err),
+            EdgeListError::Parse { line, ref message } => {
+                write!(f, "line {}: {}", line, message)
+            }
+            EdgeListError::Graph { line, err } => write!(f, "line {}: {}", line, err),
+        }
+    }
+}
+
+impl error::Error for EdgeListError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            EdgeListError::Io(ref err) => Some(err),
+            EdgeListError::Parse { .. } => None,
+            EdgeListError::Graph { ref err, .. } => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for EdgeListError {
+    fn from(err: io::Error) -> EdgeListError {
+        EdgeListError::Io(err)
+    }
+}
+
+/**
+ * Reads a whitespace-separated edge list such as the Twitter follower graph
+ * (twitter_rv.net) into any graph. Each line holds a source id, a target id
+ * and an optional integer weight, which defaults to 1. Blank lines and lines
+ * starting with '#' are skipped.
+ *
+ * A node carrying its external id is added the first time that id is seen.
+ * Returns the external id -> index map, or the line at which the graph
+ * refused a node or edge, e.g. a repeated edge in a graph without parallel
+ * edges.
+ */
+pub fn read_edge_list<R, G>(reader: R, graph: &mut G)
+    -> Result<HashMap<u32, G::TIndex>, EdgeListError>
+    where R: BufRead, G: Graph<u32, i32>
+{
+    let mut nodes: HashMap<u32, G::TIndex> = HashMap::new();
+
+    for (i, readline) in reader.lines().enumerate() {
+        let line = readline?;
+        let line_no = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let elts: Vec<&str> = trimmed.split_whitespace().collect();
+        if elts.len() < 2 || elts.len() > 3 {
+            return Err(EdgeListError::Parse {
+                line: line_no,
+                message: format!("expected 2 or 3 columns, found {}", elts.len()),
+            });
+        }
+        let src: u32 = parse_column(elts[0], "source", line_no)?;
+        let tgt: u32 = parse_column(elts[1], "target", line_no)?;
+        let weight: i32 = match elts.get(2) {
+            Some(w) => parse_column(w, "weight", line_no)?,
+            None => 1,
+        };
+
+        let src_idx = node_for(graph, &mut nodes, src, line_no)?;
+        let tgt_idx = node_for(graph, &mut nodes, tgt, line_no)?;
+        graph.try_add_edge(src_idx, tgt_idx, weight)
+            .map_err(|err| EdgeListError::Graph { line: line_no, err })?;
+    }
+
+    Ok(nodes)
+}
+
+// Returns the index of the node carrying an external id, adding the node
+// the first time the id is seen.
+fn node_for<G>(graph: &mut G, nodes: &mut HashMap<u32, G::TIndex>, id: u32, line: usize)
+    -> Result<G::TIndex, EdgeListError>
+    where G: Graph<u32, i32>
+{
+    if let Some(&idx) = nodes.get(&id) {
+        return Ok(idx);
+    }
+    let idx = graph.try_add_node(id).map_err(|err| EdgeListError::Graph { line, err })?;
+    nodes.insert(id, idx);
+    Ok(idx)
+}
+
+// source: filepath to twitter data eg. "path/to/twitter_rv.net"
+pub fn load_edge_list<P, G>(source: P, graph: &mut G)
+    -> Result<HashMap<u32, G::TIndex>, EdgeListError>
+    where P: AsRef<Path>, G: Graph<u32, i32>
+{
+    let reader = BufReader::new(File::open(source)?);
+    read_edge_list(reader, graph)
+}
+
+fn parse_column<T: ::std::str::FromStr>(column: &str, name: &str, line: usize)
+    -> Result<T, EdgeListError>
+{
+    column.parse().map_err(|_| EdgeListError::Parse {
+        line,
+        message: format!("malformed {} '{}'", name, column),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    #[test]
+    fn reads_weights_and_skips_comments() {
+        let input = "# follower graph\n1 2\n\n2 3 -4\n1 3\n";
+        let mut g = AdjListGraph::new();
+        let ids = read_edge_list(input.as_bytes(), &mut g).unwrap();
+
+        assert_eq!((g.order(), g.size()), (3, 3));
+        assert_eq!(g.get_node_data(ids[&2]), Some(&2));
+        let e = g.get_edge(ids[&2], ids[&3]).unwrap();
+        assert_eq!(g.get_edge_data(e), Some(&-4));
+        let e = g.get_edge(ids[&1], ids[&3]).unwrap();
+        assert_eq!(g.get_edge_data(e), Some(&1));
+    }
+
+    #[test]
+    fn reports_line_of_malformed_input() {
+        let input = "1 2\n2 x\n";
+        let mut g = AdjListGraph::new();
+        match read_edge_list(input.as_bytes(), &mut g) {
+            Err(EdgeListError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match read_edge_list("1 2 3 4\n".as_bytes(), &mut g) {
+            Err(EdgeListError::Parse { line, .. }) => assert_eq!(line, 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_line_of_rejected_edge() {
+        let mut g = AdjListGraph::with_parallel_edges(false);
+        match read_edge_list("1 2\n2 3\n1 2 5\n".as_bytes(), &mut g) {
+            Err(EdgeListError::Graph { line, err }) => {
+                assert_eq!((line, err), (3, GraphError::DuplicateEdge));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(g.size(), 2);
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let mut g = AdjListGraph::new();
+        match load_edge_list("does_not_exist.net", &mut g) {
+            Err(EdgeListError::Io(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
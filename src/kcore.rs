@@ -0,0 +1,223 @@
+use adjlistgraph::AdjListGraph;
+use graph::Graph;
+use std::collections::HashMap;
+use std::hash::Hash;
+use traversal::{neighbors, Direction};
+
+/**
+ * Core numbers of every node. The k-core of a graph is the largest subgraph
+ * in which every node has degree at least k; a node's core number is the
+ * largest k whose k-core contains it.
+ */
+#[derive(Debug, Clone)]
+pub struct CoreDecomposition<TIndex: Hash + Eq> {
+    core: HashMap<TIndex, usize>,
+    order: Vec<TIndex>,
+}
+
+impl<TIndex: Copy + Hash + Eq> CoreDecomposition<TIndex> {
+    pub fn core_number(&self, node: TIndex) -> Option<usize> {
+        self.core.get(&node).cloned()
+    }
+
+    pub fn core_numbers(&self) -> &HashMap<TIndex, usize> {
+        &self.core
+    }
+
+    // The largest core number, or zero for an empty graph
+    pub fn degeneracy(&self) -> usize {
+        self.core.values().cloned().max().unwrap_or(0)
+    }
+
+    // Returns the nodes in the order they were peeled off. Every node has at
+    // most degeneracy() neighbors later in the order, counted in the degree
+    // the decomposition was made for.
+    pub fn ordering(&self) -> &[TIndex] {
+        &self.order
+    }
+
+    // Returns the nodes of the k-core.
+    pub fn k_core(&self, k: usize) -> Vec<TIndex> {
+        self.order.iter().cloned().filter(|n| self.core[n] >= k).collect()
+    }
+}
+
+// Computes core numbers with the Batagelj-Zaversnik algorithm in
+// O(order + size) time, repeatedly removing a node of least remaining degree.
+// Direction picks the degree: Outgoing for outdegree, Incoming for indegree
+// and Both for total degree. Self-loops are not counted, as a node cannot
+// hold itself in a core.
+pub fn core_decomposition<N, E, G>(graph: &G, direction: Direction) -> CoreDecomposition<G::TIndex>
+    where G: Graph<N, E>
+{
+    let nodes: Vec<G::TIndex> = graph.nodes().collect();
+    let slot: HashMap<G::TIndex, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let n = nodes.len();
+    let mut degree: Vec<usize> = nodes.iter()
+        .map(|&v| neighbors(graph, v, direction).filter(|&u| u != v).count())
+        .collect();
+
+    // Bucket sort the nodes by degree. bin[d] is where the nodes of degree d
+    // start in vert, and pos is the inverse of vert.
+    let max_degree = degree.iter().cloned().max().unwrap_or(0);
+    let mut bin = vec![0; max_degree + 1];
+    for &d in &degree {
+        bin[d] += 1;
+    }
+    let mut start = 0;
+    for count in bin.iter_mut() {
+        let c = *count;
+        *count = start;
+        start += c;
+    }
+    let mut vert = vec![0; n];
+    let mut pos = vec![0; n];
+    for v in 0..n {
+        pos[v] = bin[degree[v]];
+        vert[pos[v]] = v;
+        bin[degree[v]] += 1;
+    }
+    for d in (1..bin.len()).rev() {
+        bin[d] = bin[d - 1];
+    }
+    if !bin.is_empty() {
+        bin[0] = 0;
+    }
+
+    // Removing v lowers the degree of the nodes whose degree counts edges
+    // to v, i.e. its neighbors in the opposite direction.
+    let reversed = direction.reversed();
+    for i in 0..n {
+        let v = vert[i];
+        for u in neighbors(graph, nodes[v], reversed) {
+            let u = slot[&u];
+            if degree[u] > degree[v] {
+                // Swap u with the first node of its degree, then shrink its
+                // bucket by one so u lands in the bucket below.
+                let du = degree[u];
+                let pu = pos[u];
+                let pw = bin[du];
+                let w = vert[pw];
+                if u != w {
+                    vert.swap(pu, pw);
+                    pos[u] = pw;
+                    pos[w] = pu;
+                }
+                bin[du] += 1;
+                degree[u] -= 1;
+            }
+        }
+    }
+
+    CoreDecomposition {
+        core: nodes.iter().cloned().zip(degree).collect(),
+        order: vert.into_iter().map(|v| nodes[v]).collect(),
+    }
+}
+
+// Extracts the k-core as a new graph. Nodes and edges carry the index of the
+// node or edge they were copied from. Self-loops are left out, matching the
+// degrees the decomposition was computed with.
+pub fn k_core_subgraph<N, E, G>(graph: &G, decomposition: &CoreDecomposition<G::TIndex>, k: usize)
+    -> AdjListGraph<G::TIndex, G::TIndex>
+    where G: Graph<N, E>
+{
+    let mut sub = AdjListGraph::new();
+    let mut copy_of = HashMap::new();
+    for node in decomposition.k_core(k) {
+        copy_of.insert(node, sub.add_node(node));
+    }
+    for edge in graph.edges() {
+        if let Some((start, end)) = graph.get_nodes_of(edge) {
+            if start == end {
+                continue;
+            }
+            if let (Some(&a), Some(&b)) = (copy_of.get(&start), copy_of.get(&end)) {
+                sub.add_edge(a, b, edge);
+            }
+        }
+    }
+    sub
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use io;
+
+    // A 4-clique {0, 1, 2, 3} with edges pointing from lower to higher node,
+    // a triangle {3, 4, 5} hanging off it and a pendant 6 off 5.
+    fn clique_and_tail() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..7).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3), (3, 4), (4, 5), (5, 3), (5, 6)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn core_numbers_by_degree() {
+        let (g, n) = clique_and_tail();
+        let total = core_decomposition(&g, Direction::Both);
+        let cores: Vec<usize> = n.iter().map(|&v| total.core_number(v).unwrap()).collect();
+        assert_eq!(cores, vec![3, 3, 3, 3, 2, 2, 1]);
+        assert_eq!(total.degeneracy(), 3);
+
+        // Every node has at most degeneracy() neighbors after it in the order.
+        let order = total.ordering();
+        for (i, &v) in order.iter().enumerate() {
+            let later = neighbors(&g, v, Direction::Both).filter(|u| order[i + 1..].contains(u)).count();
+            assert!(later <= total.degeneracy());
+        }
+
+        // Following edges only, the clique thins out towards node 3.
+        let out = core_decomposition(&g, Direction::Outgoing);
+        assert_eq!(out.core_number(n[0]), Some(1));
+        assert_eq!(out.core_number(n[3]), Some(1));
+        assert_eq!(out.core_number(n[6]), Some(0));
+        let inward = core_decomposition(&g, Direction::Incoming);
+        assert_eq!(inward.core_number(n[0]), Some(0));
+        assert_eq!(inward.degeneracy(), 1);
+    }
+
+    #[test]
+    fn self_loops_do_not_count() {
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let lonely = g.add_node(());
+        g.add_edge(lonely, lonely, ());
+        for &direction in &[Direction::Both, Direction::Outgoing, Direction::Incoming] {
+            let cores = core_decomposition(&g, direction);
+            assert_eq!(cores.core_number(lonely), Some(0));
+            assert_eq!(cores.degeneracy(), 0);
+        }
+
+        let (mut g, n) = clique_and_tail();
+        g.add_edge(n[6], n[6], ());
+        g.add_edge(n[4], n[4], ());
+        let total = core_decomposition(&g, Direction::Both);
+        assert_eq!(total.core_number(n[6]), Some(1));
+        assert_eq!(total.core_number(n[4]), Some(2));
+        assert_eq!(k_core_subgraph(&g, &total, 2).size(), 6 + 3);
+    }
+
+    #[test]
+    fn extract_k_core() {
+        let (g, n) = clique_and_tail();
+        let total = core_decomposition(&g, Direction::Both);
+        let core = k_core_subgraph(&g, &total, 3);
+        assert_eq!(core.order(), 4);
+        assert_eq!(core.size(), 6);
+        for node in core.nodes() {
+            assert!(n[..4].contains(core.get_node_data(node).unwrap()));
+        }
+        assert_eq!(k_core_subgraph(&g, &total, 2).order(), 6);
+        assert_eq!(k_core_subgraph(&g, &total, 4).order(), 0);
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        let star = core_decomposition(&twitter, Direction::Both);
+        assert_eq!(star.degeneracy(), 1);
+        assert!(star.core_numbers().values().all(|&k| k == 1));
+    }
+}
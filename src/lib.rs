@@ -1,117 +1,112 @@
-#![feature(alloc_system)]
-#![feature(rustc_private)]
+mod arena;
+mod rng;
+
+pub mod graph;
+pub mod adjlistgraph;
+pub mod arenagraph;
+pub mod rcgraph;
+pub mod io;
+pub mod traversal;
+pub mod weight;
+pub mod shortest_path;
+pub mod unionfind;
+pub mod components;
+pub mod dag;
+pub mod ranking;
+pub mod centrality;
+pub mod clustering;
+pub mod kcore;
+pub mod community;
+pub mod mst;
+pub mod flow;
+pub mod matching;
+pub mod biconnected;
+pub mod dominators;
 
-extern crate alloc_system;
-extern crate arena;
-
-mod graph;
-mod adjlistgraph;
-mod arenagraph;
-mod rcgraph;
-
-use std::io::{BufRead, BufReader};
-use std::fs::File;
-use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::RefCell;
-
-use graph::Graph;
-
-
-// source: filepath to twitter data eg. "path/to/twitter_rv.net"
-fn _make_twitter_graph<G: Graph<u32, i32>>(source: &str, graph: &mut G) {
-    let reader = BufReader::new(File::open(source).unwrap());
-    let mut nodes: HashMap<u32, G::TIndex> = HashMap::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::Graph;
 
-    for readline in reader.lines() {
-        let line = readline.ok().expect("read error");
-        let elts: Vec<&str> = line[..].split_whitespace().collect();
-        let src: u32 = elts[0].parse().ok().expect("malformed src");
-        let tgt: u32 = elts[1].parse().ok().expect("malformed tgt");
-        if !nodes.contains_key(&src) {
-            nodes.insert(src, graph.add_node(src));
-        }
-        if !nodes.contains_key(&tgt) {
-            nodes.insert(tgt, graph.add_node(tgt));
-        }
-        graph.add_edge(*nodes.get(&src).unwrap(), *nodes.get(&tgt).unwrap(), 1);
+    #[test]
+    fn rcgraph() {
+        let mut g = rcgraph::RcGraph::new();
+        io::load_edge_list("twitter_500.net", &mut g).unwrap();
+        assert!(g.order() != 0);
+        assert!(g.size() != 0);
     }
-}
-
-fn make_twitter_rcgraph(source: &str, graph: &mut rcgraph::RcGraph<u32, i32>) {
-    let reader = BufReader::new(File::open(source).unwrap());
-    let mut nodes: HashMap<u32, Rc<RefCell<rcgraph::Node<u32, i32>>>>
-        = HashMap::new();
 
-    for readline in reader.lines() {
-        let line = readline.ok().expect("read error");
-        let elts: Vec<&str> = line[..].split_whitespace().collect();
-        let src: u32 = elts[0].parse().ok().expect("malformed src");
-        let tgt: u32 = elts[1].parse().ok().expect("malformed tgt");
-        if !nodes.contains_key(&src) {
-            nodes.insert(src, graph.add_node(src));
-        }
-        if !nodes.contains_key(&tgt) {
-            nodes.insert(tgt, graph.add_node(tgt));
-        }
-        graph.add_edge(nodes.get(&src).unwrap().clone(), nodes.get(&tgt).unwrap().clone(), 1);
+    #[test]
+    fn arenagraph() {
+        let mut g = arenagraph::ArenaGraph::new();
+        io::load_edge_list("twitter_500.net", &mut g).unwrap();
+        assert!(g.order() != 0);
+        assert!(g.size() != 0);
     }
-}
-
-fn make_twitter_arenagraph<'a>(
-    source: &str,
-    graph: &'a mut arenagraph::ArenaGraph<'a, u32, u32>,
-) {
-	let reader = BufReader::new(File::open(source).unwrap());
-    let mut nodes: HashMap<u32, &arenagraph::Node<'a, u32, u32>>
-        = HashMap::new();
 
-    for readline in reader.lines() {
-        let line = readline.ok().expect("read error");
-        let elts: Vec<&str> = line[..].split_whitespace().collect();
-        let source_id: u32 = elts[0].parse().ok().expect("malformed src");
-        let target_id: u32 = elts[1].parse().ok().expect("malformed tgt");
+    #[test]
+    fn idxgraph() {
+        let mut g = adjlistgraph::AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut g).unwrap();
+        assert!(g.order() != 0);
+        assert!(g.size() != 0);
+    }
 
-        if !nodes.contains_key(&source_id) {
-            nodes.insert(source_id, graph.add_node(source_id));
+    // Doubles every edge weight in place and stores each node's weighted
+    // outdegree on the node itself.
+    fn weighted_outdegrees<G: Graph<u32, i32>>(g: &mut G) {
+        for (_, weight) in g.edges_mut() {
+            *weight *= 2;
         }
-        if !nodes.contains_key(&target_id) {
-            nodes.insert(target_id, graph.add_node(target_id));
+        let nodes: Vec<G::TIndex> = g.nodes().collect();
+        for n in nodes {
+            let total: i32 = g.get_succ_edges_of(n)
+                .map(|e| *g.get_edge_data(e).unwrap())
+                .sum();
+            *g.get_node_data_mut(n).unwrap() = total as u32;
         }
-
-        let source_node = nodes.get(&source_id).unwrap();
-        let end_node = nodes.get(&target_id).unwrap();
-
-        graph.add_edge(source_node, end_node, 1);
     }
-}
 
+    fn check_weighted_outdegrees<G: Graph<u32, i32>>(mut g: G) {
+        let ids = io::load_edge_list("twitter_500.net", &mut g).unwrap();
+        weighted_outdegrees(&mut g);
+        assert_eq!(g.get_node_data(ids[&12]), Some(&1000));
+        assert_eq!(g.get_node_data(ids[&13]), Some(&0));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let e = g.get_edge(ids[&12], ids[&13]).unwrap();
+        *g.get_edge_data_mut(e).unwrap() = 7;
+        assert_eq!(g.get_edge_data(e), Some(&7));
+        assert_eq!(g.nodes_mut().filter(|(_, d)| **d > 0).count(), 1);
+    }
 
     #[test]
-    fn rcgraph() {
-        let mut g = rcgraph::RcGraph::new();
-        make_twitter_rcgraph("twitter_500k.net", &mut g);
-        assert!(g.order() != 0);
-        assert!(g.size() != 0);
+    fn mutable_payloads() {
+        check_weighted_outdegrees(adjlistgraph::AdjListGraph::new());
+        check_weighted_outdegrees(rcgraph::RcGraph::new());
+        check_weighted_outdegrees(arenagraph::ArenaGraph::new());
     }
 
-    #[test]
-    fn arenagraph() {
-        let mut g = arenagraph::ArenaGraph::new();
-        make_twitter_arenagraph("twitter_500k.net", &mut g);
-        // assert!(g.order() != 0);
-        // assert!(g.size() != 0);
+    // The Twitter sample is a star around node 12, so it has no triangles
+    // until two of the followed accounts are linked.
+    fn check_triangles<G: Graph<u32, i32>>(mut g: G) {
+        let ids = io::load_edge_list("twitter_500.net", &mut g).unwrap();
+        assert_eq!(clustering::count_triangles(&g), 0);
+        assert_eq!(clustering::directed_triangles(&g), clustering::DirectedTriangles::default());
+        assert_eq!(clustering::transitivity(&g), 0.0);
+        assert_eq!(clustering::average_clustering(&g), 0.0);
+
+        g.add_edge(ids[&13], ids[&14], 1);
+        assert_eq!(clustering::count_triangles(&g), 1);
+        assert_eq!(clustering::node_triangles(&g)[&ids[&12]], 1);
+        assert_eq!(clustering::directed_triangles(&g).transitive, 1);
+        assert_eq!(clustering::local_clustering(&g)[&ids[&13]], 1.0);
+        assert!(clustering::transitivity(&g) > 0.0);
     }
 
-    // #[test]
-    // fn idxgraph() {
-    //     let mut g = adjlistgraph::AdjListGraph::new();
-    //     make_twitter_graph("twitter_500k.net", &mut g);
-    //     assert!(g.order() != 0);
-    //     assert!(g.size() != 0);
-    // }
+    #[test]
+    fn twitter_triangles() {
+        check_triangles(adjlistgraph::AdjListGraph::new());
+        check_triangles(rcgraph::RcGraph::new());
+        check_triangles(arenagraph::ArenaGraph::new());
+    }
 }
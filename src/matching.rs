@@ -0,0 +1,437 @@
+use graph::Graph;
+use std::collections::{HashMap, VecDeque};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Neg;
+use traversal::{incident_edges, neighbors, Direction};
+use weight::Weight;
+
+// Matching works on the undirected view of the graph: an edge links its two
+// endpoints whichever way it points.
+
+// The two sides of a bipartite graph. Every edge runs between them. Within
+// each connected component the side with the first node found is left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bipartition<TIndex> {
+    pub left: Vec<TIndex>,
+    pub right: Vec<TIndex>,
+}
+
+/**
+ * Error returned when a graph that should be bipartite is not. The odd cycle
+ * proving it is listed in order around the cycle, without repeating its
+ * first node at the end; a self-loop is a cycle of one node.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OddCycle<TIndex> {
+    nodes: Vec<TIndex>,
+}
+
+impl<TIndex> OddCycle<TIndex> {
+    pub fn nodes(&self) -> &[TIndex] {
+        &self.nodes
+    }
+
+    pub fn into_nodes(self) -> Vec<TIndex> {
+        self.nodes
+    }
+}
+
+impl<TIndex: fmt::Debug> fmt::Display for OddCycle<TIndex> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "graph is not bipartite, it has the odd cycle {:?}", self.nodes)
+    }
+}
+
+impl<TIndex: fmt::Debug> error::Error for OddCycle<TIndex> {}
+
+// Why min_cost_assignment found no assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssignmentError<TIndex> {
+    NotBipartite(OddCycle<TIndex>),
+
+    // The smaller side cannot be matched completely
+    Infeasible,
+}
+
+impl<TIndex: fmt::Debug> fmt::Display for AssignmentError<TIndex> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssignmentError::NotBipartite(ref cycle) => cycle.fmt(f),
+            AssignmentError::Infeasible => f.write_str("no assignment covers the smaller side"),
+        }
+    }
+}
+
+impl<TIndex: fmt::Debug> error::Error for AssignmentError<TIndex> {}
+
+impl<TIndex> From<OddCycle<TIndex>> for AssignmentError<TIndex> {
+    fn from(cycle: OddCycle<TIndex>) -> AssignmentError<TIndex> {
+        AssignmentError::NotBipartite(cycle)
+    }
+}
+
+// Minimum-cost assignment: the matched edges and their total cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment<TIndex, W> {
+    pub edges: Vec<TIndex>,
+    pub total: W,
+}
+
+// Two-colors the graph by breadth-first search. Fails with an odd cycle if
+// some edge joins two nodes of the same color.
+pub fn bipartition<N, E, G>(graph: &G) -> Result<Bipartition<G::TIndex>, OddCycle<G::TIndex>>
+    where G: Graph<N, E>
+{
+    // Color and depth of every node reached, and the node it was reached from
+    let mut seen: HashMap<G::TIndex, (bool, usize)> = HashMap::with_capacity(graph.order());
+    let mut parent: HashMap<G::TIndex, G::TIndex> = HashMap::new();
+    let mut sides = Bipartition { left: Vec::new(), right: Vec::new() };
+    let mut queue = VecDeque::new();
+
+    for root in graph.nodes() {
+        if seen.contains_key(&root) {
+            continue;
+        }
+        seen.insert(root, (false, 0));
+        queue.push_back(root);
+        while let Some(u) = queue.pop_front() {
+            let (color, depth) = seen[&u];
+            if color {
+                sides.right.push(u);
+            } else {
+                sides.left.push(u);
+            }
+            for v in neighbors(graph, u, Direction::Both) {
+                match seen.get(&v) {
+                    None => {
+                        seen.insert(v, (!color, depth + 1));
+                        parent.insert(v, u);
+                        queue.push_back(v);
+                    }
+                    Some(&(other, _)) if other == color => {
+                        return Err(odd_cycle(u, v, &parent, &seen));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    Ok(sides)
+}
+
+// Two nodes of the same color in a breadth-first tree sit at the same depth,
+// so climbing from both in step meets at their lowest common ancestor. The
+// two branches and the edge between u and v form an odd cycle.
+fn odd_cycle<T: Copy + Hash + Eq>(u: T, v: T, parent: &HashMap<T, T>, seen: &HashMap<T, (bool, usize)>)
+    -> OddCycle<T>
+{
+    debug_assert_eq!(seen[&u].1, seen[&v].1);
+    let (mut a, mut b) = (u, v);
+    let mut up = vec![a];
+    let mut down = Vec::new();
+    while a != b {
+        down.push(b);
+        a = parent[&a];
+        b = parent[&b];
+        up.push(a);
+    }
+    down.reverse();
+    up.extend(down);
+    OddCycle { nodes: up }
+}
+
+// Computes a maximum cardinality matching with the Hopcroft-Karp algorithm
+// in O(size * sqrt(order)) time. Returns the matched edges, no two of which
+// share an endpoint, or an odd cycle if the graph is not bipartite.
+pub fn maximum_matching<N, E, G>(graph: &G) -> Result<Vec<G::TIndex>, OddCycle<G::TIndex>>
+    where G: Graph<N, E>
+{
+    let sides = bipartition(graph)?;
+    let right_slot: HashMap<G::TIndex, usize> = sides.right.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let adj: Vec<Vec<(usize, G::TIndex)>> = sides.left.iter()
+        .map(|&u| {
+            incident_edges(graph, u, Direction::Both)
+                .filter_map(|(e, v)| right_slot.get(&v).map(|&r| (r, e)))
+                .collect()
+        })
+        .collect();
+
+    let n = adj.len();
+    let mut match_left: Vec<Option<(usize, G::TIndex)>> = vec![None; n];
+    let mut match_right: Vec<Option<usize>> = vec![None; sides.right.len()];
+    let mut dist = vec![usize::MAX; n];
+    let mut next = vec![0; n];
+    let mut stack = Vec::new();
+
+    loop {
+        // Layer the free left nodes and whatever alternating paths reach
+        let mut queue = VecDeque::new();
+        for l in 0..n {
+            dist[l] = if match_left[l].is_none() { queue.push_back(l); 0 } else { usize::MAX };
+        }
+        let mut found = false;
+        while let Some(l) = queue.pop_front() {
+            for &(r, _) in &adj[l] {
+                match match_right[r] {
+                    None => found = true,
+                    Some(l2) if dist[l2] == usize::MAX => {
+                        dist[l2] = dist[l] + 1;
+                        queue.push_back(l2);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        if !found {
+            break;
+        }
+
+        // Augment along vertex-disjoint shortest paths, depth-first but with
+        // an explicit stack so that long paths cannot overflow.
+        for x in next.iter_mut() {
+            *x = 0;
+        }
+        for start in 0..n {
+            if match_left[start].is_some() {
+                continue;
+            }
+            stack.clear();
+            stack.push(start);
+            while let Some(&l) = stack.last() {
+                if next[l] == adj[l].len() {
+                    dist[l] = usize::MAX;
+                    stack.pop();
+                    if let Some(&prev) = stack.last() {
+                        next[prev] += 1;
+                    }
+                    continue;
+                }
+                let (r, _) = adj[l][next[l]];
+                match match_right[r] {
+                    None => {
+                        for &l in &stack {
+                            let (r, e) = adj[l][next[l]];
+                            match_left[l] = Some((r, e));
+                            match_right[r] = Some(l);
+                        }
+                        break;
+                    }
+                    Some(l2) if dist[l2] == dist[l] + 1 => stack.push(l2),
+                    Some(_) => next[l] += 1,
+                }
+            }
+        }
+    }
+
+    Ok(match_left.into_iter().filter_map(|m| m.map(|(_, e)| e)).collect())
+}
+
+// Solves the assignment problem with the Hungarian algorithm in O(n^2 m)
+// time for sides of n <= m nodes: every node on the smaller side is matched
+// to a distinct node on the other side so that the total edge cost is as low
+// as possible. Of parallel edges only the cheapest is considered. The sides
+// are those of bipartition, so on a disconnected graph which nodes end up on
+// the smaller side depends on how each component was colored. The dual
+// potentials go negative even when every cost is positive, so W must be a
+// signed type; map unsigned costs into one in the closure.
+pub fn min_cost_assignment<N, E, G, W, F>(graph: &G, mut cost: F)
+    -> Result<Assignment<G::TIndex, W>, AssignmentError<G::TIndex>>
+    where G: Graph<N, E>, W: Weight + Neg<Output = W>, F: FnMut(&E) -> W
+{
+    let sides = bipartition(graph)?;
+    let (rows, cols) = if sides.left.len() <= sides.right.len() {
+        (sides.left, sides.right)
+    } else {
+        (sides.right, sides.left)
+    };
+    let col_slot: HashMap<G::TIndex, usize> = cols.iter().enumerate().map(|(j, &c)| (c, j + 1)).collect();
+    let (n, m) = (rows.len(), cols.len());
+
+    // Cheapest edge for every row and column, 1-based to leave room for the
+    // dummy column 0 the algorithm starts from
+    let mut best: Vec<HashMap<usize, (W, G::TIndex)>> = vec![HashMap::new(); n + 1];
+    for (i, &row) in rows.iter().enumerate() {
+        for (e, other) in incident_edges(graph, row, Direction::Both) {
+            let (j, c) = match (col_slot.get(&other), graph.get_edge_data(e)) {
+                (Some(&j), Some(data)) => (j, cost(data)),
+                _ => continue,
+            };
+            let entry = best[i + 1].entry(j).or_insert((c, e));
+            if c < entry.0 {
+                *entry = (c, e);
+            }
+        }
+    }
+
+    let zero = W::zero();
+    let mut u = vec![zero; n + 1];
+    let mut v = vec![zero; m + 1];
+    let mut row_of = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut j0 = 0;
+        let mut min_reduced: Vec<Option<W>> = vec![None; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = row_of[j0];
+            let mut delta: Option<(W, usize)> = None;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                if let Some(&(c, _)) = best[i0].get(&j) {
+                    let reduced = c - u[i0] - v[j];
+                    if min_reduced[j].is_none_or(|old| reduced < old) {
+                        min_reduced[j] = Some(reduced);
+                        way[j] = j0;
+                    }
+                }
+                if let Some(r) = min_reduced[j] {
+                    if delta.is_none_or(|(d, _)| r < d) {
+                        delta = Some((r, j));
+                    }
+                }
+            }
+            let (delta, j1) = match delta {
+                Some(d) => d,
+                None => return Err(AssignmentError::Infeasible),
+            };
+            for j in 0..=m {
+                if used[j] {
+                    u[row_of[j]] = u[row_of[j]] + delta;
+                    v[j] = v[j] - delta;
+                } else if let Some(r) = min_reduced[j] {
+                    min_reduced[j] = Some(r - delta);
+                }
+            }
+            j0 = j1;
+            if row_of[j0] == 0 {
+                break;
+            }
+        }
+        while j0 != 0 {
+            let j1 = way[j0];
+            row_of[j0] = row_of[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut assignment = Assignment { edges: Vec::with_capacity(n), total: zero };
+    for j in 1..=m {
+        if row_of[j] != 0 {
+            let (c, e) = best[row_of[j]][&j];
+            assignment.edges.push(e);
+            assignment.total = assignment.total + c;
+        }
+    }
+    Ok(assignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+    use io;
+
+    fn from_edges(n: usize, edges: &[(usize, usize, i32)]) -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let ids: Vec<i64> = (0..n).map(|_| g.add_node(())).collect();
+        for &(a, b, w) in edges {
+            g.add_edge(ids[a], ids[b], w);
+        }
+        (g, ids)
+    }
+
+    #[test]
+    fn detect_bipartite_graphs() {
+        let (g, n) = from_edges(6, &[(0, 1, 0), (2, 1, 0), (2, 3, 0), (3, 0, 0), (4, 5, 0)]);
+        let sides = bipartition(&g).unwrap();
+        assert_eq!(sides.left.len() + sides.right.len(), 6);
+        let left_of = |v: i64| sides.left.contains(&v);
+        assert!(left_of(n[0]) == left_of(n[2]) && left_of(n[0]) != left_of(n[1]));
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        let star = bipartition(&twitter).unwrap();
+        assert_eq!(star.left.len().min(star.right.len()), 1);
+
+        // A 5-cycle hanging off node 0 by a path
+        let (g, n) = from_edges(7, &[(0, 1, 0), (1, 2, 0), (2, 3, 0), (3, 4, 0), (4, 5, 0), (5, 6, 0), (6, 2, 0)]);
+        let cycle = bipartition(&g).unwrap_err().into_nodes();
+        assert_eq!(cycle.len(), 5);
+        assert!(!cycle.contains(&n[0]) && !cycle.contains(&n[1]));
+        for (i, &a) in cycle.iter().enumerate() {
+            let b = cycle[(i + 1) % cycle.len()];
+            assert!(g.has_edge_between(a, b) || g.has_edge_between(b, a));
+        }
+
+        let (g, n) = from_edges(1, &[(0, 0, 0)]);
+        assert_eq!(bipartition(&g).unwrap_err().into_nodes(), vec![n[0]]);
+    }
+
+    #[test]
+    fn hopcroft_karp() {
+        // Greedy picking 0-5 first would block 1, which only likes 5.
+        let (g, n) = from_edges(8, &[(0, 5, 0), (0, 6, 0), (1, 5, 0), (2, 6, 0), (2, 7, 0), (3, 7, 0), (4, 7, 0)]);
+        let matching = maximum_matching(&g).unwrap();
+        assert_eq!(matching.len(), 3);
+        let mut used = Vec::new();
+        for &e in &matching {
+            let (a, b) = g.get_nodes_of(e).unwrap();
+            used.push(a);
+            used.push(b);
+        }
+        used.sort();
+        used.dedup();
+        assert_eq!(used.len(), 6);
+        assert!(used.contains(&n[5]) && used.contains(&n[6]) && used.contains(&n[7]));
+
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        assert_eq!(maximum_matching(&twitter).unwrap().len(), 1);
+
+        let (g, _) = from_edges(3, &[(0, 1, 0), (1, 2, 0), (2, 0, 0)]);
+        assert!(maximum_matching(&g).is_err());
+    }
+
+    #[test]
+    fn hungarian_assignment() {
+        // Workers 0..3, jobs 3..6; the cheapest plan is 0-4, 1-3, 2-5 for 5.
+        let costs = [[4, 1, 3], [2, 0, 5], [3, 2, 2]];
+        let mut edges = Vec::new();
+        for (w, row) in costs.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                edges.push((w, 3 + j, c));
+            }
+        }
+        let (g, _) = from_edges(6, &edges);
+        let assignment = min_cost_assignment(&g, |&c| c).unwrap();
+        assert_eq!(assignment.total, 5);
+        assert_eq!(assignment.edges.len(), 3);
+
+        // Two workers, three jobs, one missing edge and a cheaper parallel edge
+        let (g, n) = from_edges(5, &[(0, 2, 7), (0, 3, 1), (1, 3, 2), (1, 4, 9), (1, 3, -4)]);
+        let assignment = min_cost_assignment(&g, |&c| c).unwrap();
+        assert_eq!(assignment.total, 7 - 4);
+        let ends: Vec<(i64, i64)> = assignment.edges.iter().map(|&e| g.get_nodes_of(e).unwrap()).collect();
+        assert!(ends.contains(&(n[0], n[2])) && ends.contains(&(n[1], n[3])));
+
+        // Non-negative costs still drive the potentials below zero.
+        let mut g: AdjListGraph<(), u32> = AdjListGraph::new();
+        let n: Vec<i64> = (0..4).map(|_| g.add_node(())).collect();
+        for &(a, b, c) in &[(0, 2, 5), (0, 3, 1), (1, 2, 2), (1, 3, 7)] {
+            g.add_edge(n[a], n[b], c);
+        }
+        assert_eq!(min_cost_assignment(&g, |&c| i64::from(c)).unwrap().total, 1 + 2);
+
+        // Workers 0 and 1 only know job 3, so either side falls short.
+        let (g, _) = from_edges(6, &[(0, 3, 1), (1, 3, 1), (2, 3, 1), (2, 4, 1), (2, 5, 1)]);
+        assert_eq!(min_cost_assignment(&g, |&c| c), Err(AssignmentError::Infeasible));
+        let (g, _) = from_edges(2, &[(0, 1, 1), (1, 1, 0)]);
+        assert!(matches!(min_cost_assignment(&g, |&c| c), Err(AssignmentError::NotBipartite(_))));
+    }
+}
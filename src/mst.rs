@@ -0,0 +1,135 @@
+use graph::Graph;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use traversal::{incident_edges, Direction};
+use unionfind::UnionFind;
+use weight::{MinScored, Weight};
+
+// Both algorithms ignore edge direction and self-loops. On a disconnected
+// graph they return a minimum spanning forest, one tree per weakly connected
+// component; the trees may differ between the two when weights tie, but the
+// total weight never does.
+
+// The edges of a minimum spanning forest and their total weight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanningForest<TIndex, W> {
+    pub edges: Vec<TIndex>,
+    pub total: W,
+}
+
+// Builds a minimum spanning forest with Kruskal's algorithm: edges are taken
+// in order of weight, skipping any that would close a cycle. Runs in
+// O(size log size).
+pub fn kruskal<N, E, G, W, F>(graph: &G, mut weight: F) -> SpanningForest<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let slot: HashMap<G::TIndex, usize> = graph.nodes().enumerate().map(|(i, n)| (n, i)).collect();
+    let mut edges: Vec<(W, G::TIndex, usize, usize)> = graph.edges()
+        .filter_map(|e| {
+            let (start, end) = graph.get_nodes_of(e)?;
+            let w = weight(graph.get_edge_data(e)?);
+            Some((w, e, slot[&start], slot[&end]))
+        })
+        .filter(|&(_, _, a, b)| a != b)
+        .collect();
+    edges.sort_by_key(|&(w, _, _, _)| w);
+
+    let mut sets = UnionFind::new(slot.len());
+    let mut forest = SpanningForest { edges: Vec::new(), total: W::zero() };
+    for (w, e, a, b) in edges {
+        if sets.union(a, b) {
+            forest.edges.push(e);
+            forest.total = forest.total + w;
+            if sets.set_count() == 1 {
+                break;
+            }
+        }
+    }
+    forest
+}
+
+// Builds a minimum spanning forest with Prim's algorithm: each tree grows
+// from a root by repeatedly adding the lightest edge leaving it, kept in a
+// binary heap. Runs in O(size log size).
+pub fn prim<N, E, G, W, F>(graph: &G, mut weight: F) -> SpanningForest<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut forest = SpanningForest { edges: Vec::new(), total: W::zero() };
+    let mut in_tree = HashSet::with_capacity(graph.order());
+    let mut heap = BinaryHeap::new();
+
+    for root in graph.nodes() {
+        if !in_tree.insert(root) {
+            continue;
+        }
+        let mut grow_from = |node: G::TIndex, heap: &mut BinaryHeap<_>, in_tree: &HashSet<G::TIndex>| {
+            for (edge, other) in incident_edges(graph, node, Direction::Both) {
+                if !in_tree.contains(&other) {
+                    if let Some(data) = graph.get_edge_data(edge) {
+                        heap.push(MinScored(weight(data), (edge, other)));
+                    }
+                }
+            }
+        };
+        grow_from(root, &mut heap, &in_tree);
+        while let Some(MinScored(w, (edge, node))) = heap.pop() {
+            if !in_tree.insert(node) {
+                continue;
+            }
+            forest.edges.push(edge);
+            forest.total = forest.total + w;
+            grow_from(node, &mut heap, &in_tree);
+        }
+    }
+    forest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+    use io;
+
+    // Two components: nodes 0 to 4 with distinct weights, edges in mixed
+    // directions and a parallel pair between 2 and 4, and the link 5 - 6
+    // with a negative self-loop on 5 that must never be picked.
+    fn two_trees() -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..7).map(|_| g.add_node(())).collect();
+        for &(a, b, w) in &[(0, 1, 4), (1, 2, 8), (3, 0, 8), (1, 3, 2), (4, 1, 6), (2, 4, 7),
+                            (3, 4, 1), (4, 2, 9), (6, 5, 3), (5, 5, -10)] {
+            g.add_edge(n[a], n[b], w);
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn kruskal_and_prim_agree() {
+        let (g, n) = two_trees();
+        let k = kruskal(&g, |&w| w);
+        let p = prim(&g, |&w| w);
+        assert_eq!(k.total, 4 + 2 + 1 + 7 + 3);
+        assert_eq!(p.total, k.total);
+        assert_eq!(k.edges.len(), n.len() - 2);
+        assert_eq!(p.edges.len(), n.len() - 2);
+
+        let mut k_edges = k.edges.clone();
+        let mut p_edges = p.edges.clone();
+        k_edges.sort();
+        p_edges.sort();
+        assert_eq!(k_edges, p_edges);
+    }
+
+    #[test]
+    fn forest_spans_every_component() {
+        let mut twitter = AdjListGraph::new();
+        io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        let forest = kruskal(&twitter, |&w| w);
+        assert_eq!(forest.edges.len(), twitter.order() - 1);
+        assert_eq!(forest.total, 500);
+        assert_eq!(prim(&twitter, |&w| w).total, 500);
+
+        let empty: AdjListGraph<(), i32> = AdjListGraph::new();
+        assert_eq!(kruskal(&empty, |&w| w), SpanningForest { edges: vec![], total: 0 });
+        assert_eq!(prim(&empty, |&w| w).edges.len(), 0);
+    }
+}
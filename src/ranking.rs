@@ -0,0 +1,494 @@
+use adjlistgraph::AdjListGraph;
+use graph::Graph;
+use rng::Rng;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Where the rank of a node without outgoing edges goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dangling {
+    // Spread it like a random jump: over every node for plain PageRank, over
+    // the seeds for personalized PageRank
+    Teleport,
+
+    // Spread it over every node, even for personalized PageRank
+    Uniform,
+
+    // Keep it on the dangling node, as if it had an edge to itself
+    SelfLoop,
+}
+
+// Settings shared by the PageRank variants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRankConfig {
+    // Probability of following an edge rather than jumping, usually 0.85
+    pub damping: f64,
+
+    // Power iteration stops once the scores move less than this in total
+    pub tolerance: f64,
+
+    // Power iteration stops after this many rounds even if not converged
+    pub max_iterations: usize,
+
+    pub dangling: Dangling,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> PageRankConfig {
+        PageRankConfig {
+            damping: 0.85,
+            tolerance: 1e-6,
+            max_iterations: 100,
+            dangling: Dangling::Teleport,
+        }
+    }
+}
+
+// The graph's successor lists renumbered to 0..order, so the inner loops run
+// over vectors instead of hash maps. Parallel edges show up more than once.
+pub(crate) struct Dense<T> {
+    pub nodes: Vec<T>,
+    pub slot: HashMap<T, usize>,
+    pub succ: Vec<Vec<usize>>,
+}
+
+impl<T: Copy + Eq + Hash> Dense<T> {
+    pub fn new<N, E, G>(graph: &G) -> Dense<T>
+        where G: Graph<N, E, TIndex = T>
+    {
+        let nodes: Vec<T> = graph.nodes().collect();
+        let slot: HashMap<T, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let succ = nodes.iter()
+            .map(|&n| graph.get_succ_nodes_of(n).map(|m| slot[&m]).collect())
+            .collect();
+        Dense { nodes, slot, succ }
+    }
+
+    // Inverts the successor lists.
+    pub fn predecessors(&self) -> Vec<Vec<usize>> {
+        let mut pred = vec![Vec::new(); self.nodes.len()];
+        for (u, succ) in self.succ.iter().enumerate() {
+            for &v in succ {
+                pred[v].push(u);
+            }
+        }
+        pred
+    }
+
+    pub fn scores<V>(&self, values: Vec<V>) -> HashMap<T, V> {
+        self.nodes.iter().cloned().zip(values).collect()
+    }
+
+    // Uniform distribution over the given nodes, skipping any that are not
+    // in the graph. None if no seed is left.
+    fn seed_vector(&self, seeds: &[T]) -> Option<Vec<f64>> {
+        let mut vector = vec![0.0; self.nodes.len()];
+        let valid: Vec<usize> = seeds.iter().filter_map(|s| self.slot.get(s).cloned()).collect();
+        if valid.is_empty() {
+            return None;
+        }
+        for &i in &valid {
+            vector[i] += 1.0 / valid.len() as f64;
+        }
+        Some(vector)
+    }
+}
+
+fn power_iteration<T>(dense: &Dense<T>, teleport: &[f64], config: &PageRankConfig) -> Vec<f64> {
+    let n = dense.nodes.len();
+    let d = config.damping;
+    let mut rank = teleport.to_vec();
+    let mut next = vec![0.0; n];
+
+    for _ in 0..config.max_iterations {
+        let mut dangling = 0.0;
+        for x in next.iter_mut() {
+            *x = 0.0;
+        }
+        for (u, succ) in dense.succ.iter().enumerate() {
+            if succ.is_empty() {
+                match config.dangling {
+                    Dangling::SelfLoop => next[u] += d * rank[u],
+                    _ => dangling += rank[u],
+                }
+                continue;
+            }
+            let share = d * rank[u] / succ.len() as f64;
+            for &v in succ {
+                next[v] += share;
+            }
+        }
+        for (v, x) in next.iter_mut().enumerate() {
+            *x += (1.0 - d) * teleport[v];
+            *x += d * dangling * match config.dangling {
+                Dangling::Uniform => 1.0 / n as f64,
+                _ => teleport[v],
+            };
+        }
+
+        let delta: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        ::std::mem::swap(&mut rank, &mut next);
+        if delta < config.tolerance {
+            break;
+        }
+    }
+    rank
+}
+
+// Computes PageRank by power iteration. The scores sum to one.
+pub fn pagerank<N, E, G>(graph: &G, config: &PageRankConfig) -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let n = dense.nodes.len();
+    let teleport = vec![1.0 / n as f64; n];
+    let rank = power_iteration(&dense, &teleport, config);
+    dense.scores(rank)
+}
+
+// Computes PageRank with every random jump landing on one of the seeds, which
+// ranks nodes by their relevance to the seeds. Seeds that are not in the
+// graph are ignored; if none is left the result is empty.
+pub fn personalized_pagerank<N, E, G>(graph: &G, seeds: &[G::TIndex], config: &PageRankConfig)
+    -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    match dense.seed_vector(seeds) {
+        Some(teleport) => {
+            let rank = power_iteration(&dense, &teleport, config);
+            dense.scores(rank)
+        }
+        None => HashMap::new(),
+    }
+}
+
+// Approximates personalized PageRank by simulating random walks from the
+// seeds, each stopping with probability 1 - damping at every step, and after
+// at most max_iterations steps just as power iteration stops after that many
+// rounds; the cap also ends walks when damping is 1. A node's score is its
+// share of all visits. Cheaper than power iteration when only
+// the neighborhood of a few seeds matters; only visited nodes appear in the
+// result. The same rng_seed always gives the same scores.
+pub fn monte_carlo_ppr<N, E, G>(graph: &G, seeds: &[G::TIndex], walks_per_seed: usize,
+                                config: &PageRankConfig, rng_seed: u64)
+    -> HashMap<G::TIndex, f64>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let starts: Vec<usize> = seeds.iter().filter_map(|s| dense.slot.get(s).cloned()).collect();
+    let mut rng = Rng::new(rng_seed);
+    let mut visits: HashMap<usize, usize> = HashMap::new();
+    let mut total = 0usize;
+
+    for &start in &starts {
+        for _ in 0..walks_per_seed {
+            let mut node = start;
+            for step in 0.. {
+                *visits.entry(node).or_insert(0) += 1;
+                total += 1;
+                if step == config.max_iterations || rng.next_f64() >= config.damping {
+                    break;
+                }
+                let succ = &dense.succ[node];
+                node = if !succ.is_empty() {
+                    succ[rng.below(succ.len())]
+                } else {
+                    match config.dangling {
+                        Dangling::Teleport => starts[rng.below(starts.len())],
+                        Dangling::Uniform => rng.below(dense.nodes.len()),
+                        Dangling::SelfLoop => node,
+                    }
+                };
+            }
+        }
+    }
+
+    visits.into_iter()
+        .map(|(i, count)| (dense.nodes[i], count as f64 / total as f64))
+        .collect()
+}
+
+// Hub and authority scores of every node. Good hubs point at many good
+// authorities, and good authorities are pointed at by many good hubs. Each
+// score map sums to one, unless the graph has no edges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HubsAuthorities<TIndex: Hash + Eq> {
+    pub hubs: HashMap<TIndex, f64>,
+    pub authorities: HashMap<TIndex, f64>,
+}
+
+// out[i] is the sum of values[j] * scale[j] over the j in lists[i].
+fn spread(values: &[f64], lists: &[Vec<usize>], scale: &[f64]) -> Vec<f64> {
+    lists.iter()
+        .map(|list| list.iter().map(|&j| values[j] * scale[j]).sum())
+        .collect()
+}
+
+// Scales values to sum to one, and returns how far they moved from previous.
+fn normalize(values: &mut [f64], previous: &[f64]) -> f64 {
+    let total: f64 = values.iter().sum();
+    if total > 0.0 {
+        for x in values.iter_mut() {
+            *x /= total;
+        }
+    }
+    values.iter().zip(previous).map(|(a, b)| (a - b).abs()).sum()
+}
+
+// Shared driver for HITS and SALSA. Each round computes new authority and hub
+// scores from the old ones via the given step functions.
+fn mutual_iteration<T, FA, FH>(dense: &Dense<T>, tolerance: f64, max_iterations: usize,
+                               initial: (Vec<f64>, Vec<f64>), authority_step: FA, hub_step: FH)
+    -> HubsAuthorities<T>
+    where T: Copy + Hash + Eq,
+          FA: Fn(&[f64]) -> Vec<f64>,
+          FH: Fn(&[f64]) -> Vec<f64>
+{
+    let (mut hubs, mut authorities) = initial;
+    for _ in 0..max_iterations {
+        let mut next_authorities = authority_step(&authorities);
+        let mut next_hubs = hub_step(&hubs);
+        let delta = normalize(&mut next_authorities, &authorities) + normalize(&mut next_hubs, &hubs);
+        authorities = next_authorities;
+        hubs = next_hubs;
+        if delta < tolerance {
+            break;
+        }
+    }
+    HubsAuthorities {
+        hubs: dense.scores(hubs),
+        authorities: dense.scores(authorities),
+    }
+}
+
+// Uniform distribution over the nodes where keep is true.
+fn uniform_where<F: Fn(usize) -> bool>(n: usize, keep: F) -> Vec<f64> {
+    let count = (0..n).filter(|&i| keep(i)).count();
+    (0..n).map(|i| if keep(i) { 1.0 / count as f64 } else { 0.0 }).collect()
+}
+
+// Computes Kleinberg's HITS scores by power iteration: a node's authority is
+// the sum of the hub scores of its predecessors, and its hub score the sum of
+// the authority of its successors.
+pub fn hits<N, E, G>(graph: &G, tolerance: f64, max_iterations: usize) -> HubsAuthorities<G::TIndex>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let pred = dense.predecessors();
+    let n = dense.nodes.len();
+    let ones = vec![1.0; n];
+    let initial = (uniform_where(n, |_| true), uniform_where(n, |_| true));
+
+    // Both scores are advanced by a full round trip, so that each one only
+    // depends on its own previous value.
+    mutual_iteration(&dense, tolerance, max_iterations, initial,
+                     |a| spread(&spread(a, &dense.succ, &ones), &pred, &ones),
+                     |h| spread(&spread(h, &pred, &ones), &dense.succ, &ones))
+}
+
+// Computes SALSA scores: like HITS, but every round trip is a random walk
+// that goes backwards along a random incoming edge and then forwards along a
+// random outgoing one (or the other way round for hubs). This damps the
+// effect of tightly knit groups that HITS tends to rank above everything else.
+pub fn salsa<N, E, G>(graph: &G, tolerance: f64, max_iterations: usize) -> HubsAuthorities<G::TIndex>
+    where G: Graph<N, E>
+{
+    let dense = Dense::new(graph);
+    let pred = dense.predecessors();
+    let n = dense.nodes.len();
+    let inverse = |lists: &[Vec<usize>]| -> Vec<f64> {
+        lists.iter().map(|l| if l.is_empty() { 0.0 } else { 1.0 / l.len() as f64 }).collect()
+    };
+    let per_in_edge = inverse(&pred);
+    let per_out_edge = inverse(&dense.succ);
+    let initial = (uniform_where(n, |i| !dense.succ[i].is_empty()), uniform_where(n, |i| !pred[i].is_empty()));
+
+    mutual_iteration(&dense, tolerance, max_iterations, initial,
+                     |a| spread(&spread(a, &dense.succ, &per_in_edge), &pred, &per_out_edge),
+                     |h| spread(&spread(h, &pred, &per_out_edge), &dense.succ, &per_in_edge))
+}
+
+// Which side of a hub/authority bipartite graph a node is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Hub,
+    Authority,
+}
+
+// Returns a user's circle of trust: the size nodes with the highest
+// personalized PageRank from that user, best first. The user is usually
+// among them.
+pub fn circle_of_trust<N, E, G>(graph: &G, user: G::TIndex, size: usize, config: &PageRankConfig)
+    -> Vec<G::TIndex>
+    where G: Graph<N, E>
+{
+    let mut ranked: Vec<(G::TIndex, f64)> = personalized_pagerank(graph, &[user], config)
+        .into_iter()
+        .filter(|&(_, score)| score > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.into_iter().take(size).map(|(node, _)| node).collect()
+}
+
+// Builds the bipartite graph used for "who to follow" recommendations. Every
+// hub gets a node on the hub side, everything a hub points at gets a node on
+// the authority side, and each edge from a hub becomes an edge from hub side
+// to authority side. A node can appear on both sides. Node data records the
+// side and the node in the original graph. Running salsa on the result and
+// ranking the authority side suggests whom the hubs should follow.
+pub fn hub_authority_graph<N, E, G>(graph: &G, hubs: &[G::TIndex]) -> AdjListGraph<(Side, G::TIndex), ()>
+    where G: Graph<N, E>
+{
+    let mut bipartite = AdjListGraph::new();
+    let mut hub_ids = HashMap::new();
+    let mut authority_ids = HashMap::new();
+    for &hub in hubs {
+        if graph.has_node(hub) && !hub_ids.contains_key(&hub) {
+            hub_ids.insert(hub, bipartite.add_node((Side::Hub, hub)));
+        }
+    }
+    for &hub in hubs {
+        let from = match hub_ids.get(&hub) {
+            Some(&from) => from,
+            None => continue,
+        };
+        for followed in graph.get_succ_nodes_of(hub) {
+            let to = *authority_ids.entry(followed)
+                .or_insert_with(|| bipartite.add_node((Side::Authority, followed)));
+            bipartite.add_edge(from, to, ());
+        }
+    }
+    bipartite
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+    use io;
+
+    // 0 -> 1 -> 2 -> 0, 2 -> 3, and 3 has no outgoing edges
+    fn cycle_with_tail() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..4).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (1, 2), (2, 0), (2, 3)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    fn total(scores: &HashMap<i64, f64>) -> f64 {
+        scores.values().sum()
+    }
+
+    #[test]
+    fn pagerank_on_small_graphs() {
+        let (g, n) = cycle_with_tail();
+        for &dangling in &[Dangling::Teleport, Dangling::Uniform, Dangling::SelfLoop] {
+            let config = PageRankConfig { dangling, tolerance: 1e-10, ..PageRankConfig::default() };
+            let scores = pagerank(&g, &config);
+            assert!((total(&scores) - 1.0).abs() < 1e-9);
+            assert!(scores[&n[0]] < scores[&n[1]] && scores[&n[1]] < scores[&n[2]]);
+        }
+
+        // On the Twitter sample node 12 follows everyone else, who follow no
+        // one, so all of them rank equally and above 12.
+        let mut twitter = AdjListGraph::new();
+        let ids = io::load_edge_list("twitter_500.net", &mut twitter).unwrap();
+        let scores = pagerank(&twitter, &PageRankConfig::default());
+        assert!(scores[&ids[&13]] > scores[&ids[&12]]);
+        assert!((scores[&ids[&13]] - scores[&ids[&14]]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn personalized_pagerank_favors_seeds() {
+        let (g, n) = cycle_with_tail();
+        let config = PageRankConfig::default();
+        let scores = personalized_pagerank(&g, &[n[3]], &config);
+        assert!((total(&scores) - 1.0).abs() < 1e-6);
+        assert!(scores[&n[3]] > 0.99);
+        assert_eq!(scores[&n[0]], 0.0);
+
+        let scores = personalized_pagerank(&g, &[n[1]], &config);
+        assert!(scores[&n[1]] > scores[&n[0]]);
+        assert!(personalized_pagerank(&g, &[99], &config).is_empty());
+    }
+
+    #[test]
+    fn monte_carlo_approximates_power_iteration() {
+        let (g, n) = cycle_with_tail();
+        let config = PageRankConfig::default();
+        let exact = personalized_pagerank(&g, &[n[0]], &config);
+        let approx = monte_carlo_ppr(&g, &[n[0]], 20_000, &config, 7);
+        for &v in &n {
+            let estimate = approx.get(&v).cloned().unwrap_or(0.0);
+            assert!((estimate - exact[&v]).abs() < 0.02, "{} vs {}", estimate, exact[&v]);
+        }
+        assert_eq!(approx, monte_carlo_ppr(&g, &[n[0]], 20_000, &config, 7));
+
+        // Without damping no walk stops on its own, so each of the three runs
+        // the full ten steps and makes 11 of the 33 visits.
+        let endless = PageRankConfig { damping: 1.0, max_iterations: 10, ..config };
+        let scores = monte_carlo_ppr(&g, &[n[0]], 3, &endless, 7);
+        assert!((scores.values().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(scores.values().all(|&p| (p * 33.0 - (p * 33.0).round()).abs() < 1e-9));
+    }
+
+    #[test]
+    fn hits_and_salsa_scores() {
+        // Hubs 0 and 1 both point at 2 and 3; hub 4 points at 3 and 5.
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 2), (0, 3), (1, 2), (1, 3), (4, 3), (4, 5)] {
+            g.add_edge(n[a], n[b], ());
+        }
+
+        let scores = hits(&g, 1e-10, 1000);
+        assert!((total(&scores.hubs) - 1.0).abs() < 1e-9);
+        assert!(scores.authorities[&n[3]] > scores.authorities[&n[2]]);
+        assert!(scores.authorities[&n[2]] > scores.authorities[&n[5]]);
+        assert!(scores.hubs[&n[0]] > scores.hubs[&n[4]]);
+        assert_eq!(scores.authorities[&n[0]], 0.0);
+
+        // On a connected graph SALSA authority is proportional to indegree.
+        let scores = salsa(&g, 1e-10, 1000);
+        for &(v, indegree) in &[(n[2], 2.0), (n[3], 3.0), (n[5], 1.0)] {
+            assert!((scores.authorities[&v] - indegree / 6.0).abs() < 1e-9);
+        }
+        assert!((scores.hubs[&n[4]] - 2.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn who_to_follow() {
+        // User 0 follows 1 and 2, who both follow 3. Node 4 is unrelated and
+        // follows 5.
+        let mut g: AdjListGraph<(), ()> = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (0, 2), (1, 3), (2, 3), (1, 2), (4, 5)] {
+            g.add_edge(n[a], n[b], ());
+        }
+
+        let circle = circle_of_trust(&g, n[0], 3, &PageRankConfig::default());
+        assert_eq!(circle.len(), 3);
+        assert_eq!(circle[0], n[0]);
+        assert!(!circle.contains(&n[4]));
+
+        let bipartite = hub_authority_graph(&g, &circle);
+        for edge in bipartite.edges() {
+            let (hub, authority) = bipartite.get_nodes_of(edge).unwrap();
+            assert_eq!(bipartite.get_node_data(hub).unwrap().0, Side::Hub);
+            assert_eq!(bipartite.get_node_data(authority).unwrap().0, Side::Authority);
+        }
+
+        let scores = salsa(&bipartite, 1e-10, 1000);
+        let mut ranked: Vec<(f64, i64)> = scores.authorities.iter()
+            .filter_map(|(&b, &score)| match *bipartite.get_node_data(b).unwrap() {
+                (Side::Authority, node) if node != n[0] && !g.has_edge_between(n[0], node) => Some((score, node)),
+                _ => None,
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        assert_eq!(ranked[0].1, n[3]);
+    }
+}
@@ -1,70 +1,108 @@
-use std::rc::Rc;
-use std::cell::RefCell;
+use graph::{Graph, GraphError};
+use std::cell::{RefCell, UnsafeCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+/**
+ * Ownership in this graph only runs one way, so that no reference cycles are
+ * formed: the graph holds the only strong handles to its nodes, nodes hold
+ * strong handles to their incident edges, and edges point back at their
+ * endpoints through Weak handles. Dropping a node therefore frees its
+ * incident edges as soon as they are unlinked from the other endpoint and the
+ * graph's edge table, and dropping the graph frees everything.
+ *
+ * Node and edge payloads sit in an UnsafeCell so that they can be handed out
+ * mutably even though the Rc handles are shared. This is sound because the
+ * handles never leave the graph: payloads are only reachable through its
+ * accessors, so &self and &mut self on the graph already rule out aliasing.
+ */
 
 /*******************************************************************/
 /*******************************************************************/
 
 #[derive(Debug)]
-pub struct Node<TNode, TEdge> {
+struct Node<TNode, TEdge> {
+    // Index handed out to users of the graph
+    idx: i64,
+
     // Associated data for this node
-    data: TNode,
+    data: UnsafeCell<TNode>,
 
     // Edges leading to predecessors and successors of this node
-    pred: Vec<Rc<RefCell<Edge<TEdge, TNode>>>>,
-    succ: Vec<Rc<RefCell<Edge<TEdge, TNode>>>>,
+    pred: RefCell<Vec<Rc<Edge<TEdge, TNode>>>>,
+    succ: RefCell<Vec<Rc<Edge<TEdge, TNode>>>>,
 }
 
-// impl<TNode, TEdge> Drop for Node<TNode, TEdge> {
-//     fn drop(&mut self) {
-//         for pred in self.pred.drain(..) {
-//             drop(pred);
-//         }
-//         for succ in self.succ.drain(..) {
-//             drop(succ);
-//         }
-//     }
-// }
-
 impl<TNode, TEdge> Node<TNode, TEdge> {
-    fn new(data: TNode) -> Node<TNode, TEdge> {
+    fn new(idx: i64, data: TNode) -> Node<TNode, TEdge> {
         Node {
-            data: data,
-            pred: Vec::new(),
-            succ: Vec::new(),
+            idx,
+            data: UnsafeCell::new(data),
+            pred: RefCell::new(Vec::new()),
+            succ: RefCell::new(Vec::new()),
         }
     }
+
+    fn remove_succ(&self, edge: &Rc<Edge<TEdge, TNode>>) {
+        self.succ.borrow_mut().retain(|e| !Rc::ptr_eq(e, edge));
+    }
+
+    fn remove_pred(&self, edge: &Rc<Edge<TEdge, TNode>>) {
+        self.pred.borrow_mut().retain(|e| !Rc::ptr_eq(e, edge));
+    }
 }
 
 /*******************************************************************/
 /*******************************************************************/
 
-#[derive(Debug)]
-pub struct Edge<TEdge, TNode> {
+struct Edge<TEdge, TNode> {
+    // Index handed out to users of the graph
+    idx: i64,
+
     // Associated data for this edge
-    data: TEdge,
+    data: UnsafeCell<TEdge>,
 
-    // Start and end nodes for this edge
-    start: Rc<RefCell<Node<TNode, TEdge>>>,
-    end: Rc<RefCell<Node<TNode, TEdge>>>,
+    // Start and end nodes for this edge. These are weak so that an edge does
+    // not keep its endpoints alive.
+    start: Weak<Node<TNode, TEdge>>,
+    end: Weak<Node<TNode, TEdge>>,
 }
 
-// impl<TEdge, TNode> Drop for Edge<TEdge, TNode> {
-//     fn drop(&mut self) {
-//         drop(self.start);
-//         drop(self.end);
-//     }
-// }
+// Nodes and edges point at each other, so only print the endpoint indices
+// instead of recursing into the nodes.
+impl<TEdge: fmt::Debug, TNode> fmt::Debug for Edge<TEdge, TNode> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Edge")
+            .field("idx", &self.idx)
+            .field("data", unsafe { &*self.data.get() })
+            .field("start", &self.start.upgrade().map(|n| n.idx))
+            .field("end", &self.end.upgrade().map(|n| n.idx))
+            .finish()
+    }
+}
 
 impl<TEdge, TNode> Edge<TEdge, TNode> {
-    fn new(data: TEdge, start: Rc<RefCell<Node<TNode, TEdge>>>, end: Rc<RefCell<Node<TNode, TEdge>>>)
+    fn new(idx: i64, data: TEdge, start: &Rc<Node<TNode, TEdge>>, end: &Rc<Node<TNode, TEdge>>)
         -> Edge<TEdge, TNode>
     {
         Edge {
-            data: data,
-            start: start,
-            end: end,
+            idx,
+            data: UnsafeCell::new(data),
+            start: Rc::downgrade(start),
+            end: Rc::downgrade(end),
         }
     }
+
+    // Edges are always detached before their endpoints are dropped, so an
+    // edge reachable from the graph can always upgrade its endpoints.
+    fn start(&self) -> Rc<Node<TNode, TEdge>> {
+        self.start.upgrade().expect("edge outlived its start node")
+    }
+
+    fn end(&self) -> Rc<Node<TNode, TEdge>> {
+        self.end.upgrade().expect("edge outlived its end node")
+    }
 }
 
 /*******************************************************************/
@@ -72,86 +110,399 @@ impl<TEdge, TNode> Edge<TEdge, TNode> {
 
 #[derive(Debug)]
 pub struct RcGraph<TNode, TEdge> {
-    nodes: Vec<Rc<RefCell<Node<TNode, TEdge>>>>,
-    edges: Vec<Rc<RefCell<Edge<TEdge, TNode>>>>,
+    cur_node_idx: i64,
+    cur_edge_idx: i64,
+
+    // Whether more than one edge may run from one node to another
+    parallel_edges: bool,
+
+    nodes: HashMap<i64, Rc<Node<TNode, TEdge>>>,
+    edges: HashMap<i64, Rc<Edge<TEdge, TNode>>>,
 }
 
-// impl<TNode, TEdge> Drop for RcGraph<TNode, TEdge> {
-//     fn drop(&mut self) {
-//         for edge in self.edges.drain(..) {
-//             drop(edge);
-//         }
-//         for node in self.nodes.drain(..) {
-//             drop(node);
-//         }
-//     }
-// }
+impl<TNode, TEdge> Default for RcGraph<TNode, TEdge> {
+    fn default() -> RcGraph<TNode, TEdge> {
+        RcGraph::new()
+    }
+}
 
 impl<TNode, TEdge> RcGraph<TNode, TEdge> {
     pub fn new() -> RcGraph<TNode, TEdge> {
+        RcGraph::with_parallel_edges(true)
+    }
+
+    // Creates a graph that either allows or rejects (with
+    // GraphError::DuplicateEdge) a second edge between the same two nodes.
+    pub fn with_parallel_edges(allow: bool) -> RcGraph<TNode, TEdge> {
         RcGraph {
-            nodes: Vec::new(),
-            edges: Vec::new(),
+            cur_node_idx: 0i64,
+            cur_edge_idx: 0i64,
+
+            parallel_edges: allow,
+
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
         }
     }
 
-    pub fn add_node(&mut self, data: TNode) -> Rc<RefCell<Node<TNode, TEdge>>> {
-        let node_idx = Rc::new(RefCell::new(Node::new(data)));
-        self.nodes.push(node_idx.clone());
-        node_idx
+    // Unlinks an edge from the graph and from both of its endpoints.
+    fn detach_edge(&mut self, edge: &Rc<Edge<TEdge, TNode>>) {
+        self.edges.remove(&edge.idx);
+        if let Some(start) = edge.start.upgrade() {
+            start.remove_succ(edge);
+        }
+        if let Some(end) = edge.end.upgrade() {
+            end.remove_pred(edge);
+        }
+    }
+}
+
+impl<TNode, TEdge> Graph<TNode, TEdge> for RcGraph<TNode, TEdge> {
+    type TIndex = i64;
+
+    fn try_add_node(&mut self, data: TNode) -> Result<Self::TIndex, GraphError> {
+        let node_idx = self.cur_node_idx;
+        self.cur_node_idx = node_idx.checked_add(1).ok_or(GraphError::CapacityExceeded)?;
+        self.nodes.insert(node_idx, Rc::new(Node::new(node_idx, data)));
+        Ok(node_idx)
     }
 
     // Removes node and all incident edges
-    pub fn remove_node(&mut self, node: Rc<RefCell<Node<TNode, TEdge>>>) -> Option<TNode> {
-        unimplemented!();
+    fn try_remove_node(&mut self, node: Self::TIndex) -> Result<TNode, GraphError> {
+        let n = self.nodes.remove(&node).ok_or(GraphError::MissingNode)?;
+
+        // Snapshot the incident edges first, since detaching mutates the
+        // adjacency lists (a self-loop shows up in both of them).
+        let mut incident: Vec<Rc<Edge<TEdge, TNode>>> = n.succ.borrow().clone();
+        incident.extend(n.pred.borrow().iter().cloned());
+        for edge in incident.drain(..) {
+            if self.edges.contains_key(&edge.idx) {
+                self.detach_edge(&edge);
+            }
+        }
+
+        // The graph held the only strong handle to the node.
+        match Rc::try_unwrap(n) {
+            Ok(n) => Ok(n.data.into_inner()),
+            Err(_) => unreachable!("node handle escaped the graph"),
+        }
     }
 
-    // Assumes given nodes exist...which is basically guaranteed
-    pub fn add_edge(&mut self, start_node: Rc<RefCell<Node<TNode, TEdge>>>, end_node: Rc<RefCell<Node<TNode, TEdge>>>, data: TEdge)
-        -> Rc<RefCell<Edge<TEdge, TNode>>>
-    {
-        let edge_idx = Rc::new(RefCell::new(Edge::new(data, start_node.clone(), end_node.clone())));
-        self.edges.push(edge_idx.clone());
-        start_node.borrow_mut().succ.push(edge_idx.clone());
-        end_node.borrow_mut().pred.push(edge_idx.clone());
+    fn try_add_edge(&mut self, start_node: Self::TIndex, end_node: Self::TIndex, data: TEdge) -> Result<Self::TIndex, GraphError> {
+        let (start, end) = match (self.nodes.get(&start_node), self.nodes.get(&end_node)) {
+            (Some(start), Some(end)) => (start.clone(), end.clone()),
+            _ => return Err(GraphError::MissingNode),
+        };
+        if !self.parallel_edges && self.has_edge_between(start_node, end_node) {
+            return Err(GraphError::DuplicateEdge);
+        }
+        let edge_idx = self.cur_edge_idx;
+        self.cur_edge_idx = edge_idx.checked_add(1).ok_or(GraphError::CapacityExceeded)?;
+
+        let edge = Rc::new(Edge::new(edge_idx, data, &start, &end));
+        start.succ.borrow_mut().push(edge.clone());
+        end.pred.borrow_mut().push(edge.clone());
+        self.edges.insert(edge_idx, edge);
 
-        edge_idx
+        Ok(edge_idx)
     }
 
-    pub fn remove_edge(&mut self, edge: Rc<RefCell<Edge<TEdge, TNode>>>) -> Option<TEdge> {
-        unimplemented!();
+    fn try_remove_edge(&mut self, edge: Self::TIndex) -> Result<TEdge, GraphError> {
+        let e = self.edges.get(&edge).cloned().ok_or(GraphError::MissingEdge)?;
+        self.detach_edge(&e);
+
+        // Detaching dropped every other strong handle to the edge.
+        match Rc::try_unwrap(e) {
+            Ok(e) => Ok(e.data.into_inner()),
+            Err(_) => unreachable!("edge handle escaped the graph"),
+        }
     }
 
-    pub fn remove_edge_between(&mut self, node_a: Rc<RefCell<Node<TNode, TEdge>>>, node_b: Rc<RefCell<Node<TNode, TEdge>>>) -> Option<TEdge> {
-        unimplemented!();
+    fn try_remove_edge_between(&mut self, node_a: Self::TIndex, node_b: Self::TIndex) -> Result<TEdge, GraphError> {
+        if !self.has_node(node_a) || !self.has_node(node_b) {
+            return Err(GraphError::MissingNode);
+        }
+        let edge = self.get_edge(node_a, node_b).ok_or(GraphError::MissingEdge)?;
+        self.try_remove_edge(edge)
     }
 
-    pub fn clear(&mut self) {
+    // Dropping the nodes drops their adjacency lists, which leaves the edge
+    // table holding the last handle to every edge.
+    fn clear(&mut self) {
         self.nodes.clear();
         self.edges.clear();
     }
 
-    pub fn order(&self) -> usize {
+    fn order(&self) -> usize {
         self.nodes.len()
     }
 
-    pub fn size(&self) -> usize {
+    fn size(&self) -> usize {
         self.edges.len()
     }
 
-    pub fn get_node_data(&self, node: Rc<RefCell<Node<TNode, TEdge>>>) -> Option<&TNode> {
-        unimplemented!();
+    fn has_node(&self, node: Self::TIndex) -> bool {
+        self.nodes.contains_key(&node)
+    }
+
+    fn get_node_data(&self, node: Self::TIndex) -> Option<&TNode> {
+        if let Some(n) = self.nodes.get(&node) {
+            Some(unsafe { &*n.data.get() })
+        } else {
+            None
+        }
+    }
+
+    fn has_edge(&self, edge: Self::TIndex) -> bool {
+        self.edges.contains_key(&edge)
+    }
+
+    fn has_edge_between(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> bool {
+        self.get_edge(node_a, node_b).is_some()
+    }
+
+    fn get_edge(&self, node_a: Self::TIndex, node_b: Self::TIndex) -> Option<Self::TIndex> {
+        if let Some(start) = self.nodes.get(&node_a) {
+            for edge in start.succ.borrow().iter() {
+                if edge.end().idx == node_b {
+                    return Some(edge.idx);
+                }
+            }
+        }
+        None
+    }
+
+    fn get_nodes_of(&self, edge: Self::TIndex) -> Option<(Self::TIndex, Self::TIndex)> {
+        self.edges.get(&edge).map(|e| (e.start().idx, e.end().idx))
+    }
+
+    fn get_degree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.nodes.get(&node).map(|n| n.pred.borrow().len() + n.succ.borrow().len())
     }
 
-    pub fn get_edge_data(&self, edge: Rc<RefCell<Edge<TEdge, TNode>>>) -> Option<&TEdge> {
-        unimplemented!();
+    fn get_indegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.nodes.get(&node).map(|n| n.pred.borrow().len())
     }
 
-    pub fn nodes<'a>(&'a self) -> Box<Iterator<Item = &'a Rc<RefCell<Node<TNode, TEdge>>>> + 'a> {
-        Box::new(self.nodes.iter())
+    fn get_outdegree_of(&self, node: Self::TIndex) -> Option<usize> {
+        self.nodes.get(&node).map(|n| n.succ.borrow().len())
+    }
+
+    fn get_edge_start(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edges.get(&edge).map(|e| e.start().idx)
+    }
+
+    fn get_edge_end(&self, edge: Self::TIndex) -> Option<Self::TIndex> {
+        self.edges.get(&edge).map(|e| e.end().idx)
+    }
+
+    fn get_edge_data(&self, edge: Self::TIndex) -> Option<&TEdge> {
+        if let Some(e) = self.edges.get(&edge) {
+            Some(unsafe { &*e.data.get() })
+        } else {
+            None
+        }
     }
 
-    pub fn edges<'a>(&'a self) -> Box<Iterator<Item = &'a Rc<RefCell<Edge<TEdge, TNode>>>> + 'a> {
-        Box::new(self.edges.iter())
+    fn get_node_data_mut(&mut self, node: Self::TIndex) -> Option<&mut TNode> {
+        if let Some(n) = self.nodes.get(&node) {
+            Some(unsafe { &mut *n.data.get() })
+        } else {
+            None
+        }
+    }
+
+    fn get_edge_data_mut(&mut self, edge: Self::TIndex) -> Option<&mut TEdge> {
+        if let Some(e) = self.edges.get(&edge) {
+            Some(unsafe { &mut *e.data.get() })
+        } else {
+            None
+        }
+    }
+
+    fn nodes<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.nodes.keys().cloned())
+    }
+
+    fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        Box::new(self.edges.keys().cloned())
+    }
+
+    // Each node and edge owns a distinct cell, so the mutable references
+    // handed out below never alias one another.
+
+    fn nodes_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TNode)> + 'a> {
+        Box::new(self.nodes.iter().map(|(&idx, n)| (idx, unsafe { &mut *n.data.get() })))
+    }
+
+    fn edges_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = (Self::TIndex, &'a mut TEdge)> + 'a> {
+        Box::new(self.edges.iter().map(|(&idx, e)| (idx, unsafe { &mut *e.data.get() })))
+    }
+
+    // The adjacency lists live behind a RefCell, so the per-node iterators
+    // below walk a snapshot of the indices rather than holding a borrow.
+
+    fn get_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            let edges: Vec<i64> = n.pred.borrow().iter()
+                .chain(n.succ.borrow().iter())
+                .map(|e| e.idx)
+                .collect();
+            Box::new(edges.into_iter())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            let edges: Vec<i64> = n.pred.borrow().iter().map(|e| e.idx).collect();
+            Box::new(edges.into_iter())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_succ_edges_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            let edges: Vec<i64> = n.succ.borrow().iter().map(|e| e.idx).collect();
+            Box::new(edges.into_iter())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_pred_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            let nodes: Vec<i64> = n.pred.borrow().iter().map(|e| e.start().idx).collect();
+            Box::new(nodes.into_iter())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+
+    fn get_succ_nodes_of<'a>(&'a self, node: Self::TIndex) ->
+                            Box<dyn Iterator<Item = Self::TIndex> + 'a> {
+        if let Some(n) = self.nodes.get(&node) {
+            let nodes: Vec<i64> = n.succ.borrow().iter().map(|e| e.end().idx).collect();
+            Box::new(nodes.into_iter())
+        } else {
+            Box::new(::std::iter::empty())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Payload that tracks how many instances are currently alive.
+    struct Tracked(Rc<Cell<usize>>);
+
+    impl Tracked {
+        fn new(live: &Rc<Cell<usize>>) -> Tracked {
+            live.set(live.get() + 1);
+            Tracked(live.clone())
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() - 1);
+        }
+    }
+
+    // Builds a small graph with a cycle, a self-loop and parallel edges.
+    fn cyclic_graph(live_nodes: &Rc<Cell<usize>>, live_edges: &Rc<Cell<usize>>)
+        -> (RcGraph<Tracked, Tracked>, Vec<i64>)
+    {
+        let mut g = RcGraph::new();
+        let nodes: Vec<i64> = (0..4).map(|_| g.add_node(Tracked::new(live_nodes))).collect();
+        for &(a, b) in &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 3), (0, 1)] {
+            g.add_edge(nodes[a], nodes[b], Tracked::new(live_edges));
+        }
+        (g, nodes)
+    }
+
+    #[test]
+    fn remove_node_detaches_incident_edges() {
+        let mut g = RcGraph::new();
+        let a = g.add_node('a');
+        let b = g.add_node('b');
+        let c = g.add_node('c');
+        g.add_edge(a, b, 1);
+        g.add_edge(b, c, 2);
+        g.add_edge(b, b, 3);
+        let ca = g.add_edge(c, a, 4).unwrap();
+
+        assert_eq!(g.remove_node(b), Some('b'));
+        assert_eq!(g.order(), 2);
+        assert_eq!(g.size(), 1);
+        assert_eq!(g.get_outdegree_of(a), Some(0));
+        assert_eq!(g.get_indegree_of(c), Some(0));
+        assert_eq!(g.get_succ_nodes_of(c).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(g.remove_edge(ca), Some(4));
+        assert_eq!(g.remove_node(b), None);
+    }
+
+    #[test]
+    fn remove_edge_between() {
+        let mut g = RcGraph::new();
+        let a = g.add_node(0);
+        let b = g.add_node(1);
+        g.add_edge(a, b, "ab");
+
+        assert_eq!(g.remove_edge_between(b, a), None);
+        assert_eq!(g.remove_edge_between(a, b), Some("ab"));
+        assert!(!g.has_edge_between(a, b));
+        assert_eq!(g.get_degree_of(a), Some(0));
+        assert_eq!(g.get_node_data(b), Some(&1));
+    }
+
+    #[test]
+    fn drop_frees_everything() {
+        let live_nodes = Rc::new(Cell::new(0));
+        let live_edges = Rc::new(Cell::new(0));
+        let (g, _) = cyclic_graph(&live_nodes, &live_edges);
+        assert_eq!((live_nodes.get(), live_edges.get()), (4, 6));
+
+        let weak_nodes: Vec<_> = g.nodes.values().map(Rc::downgrade).collect();
+        let weak_edges: Vec<_> = g.edges.values().map(Rc::downgrade).collect();
+        drop(g);
+
+        assert_eq!((live_nodes.get(), live_edges.get()), (0, 0));
+        assert!(weak_nodes.iter().all(|n| n.upgrade().is_none()));
+        assert!(weak_edges.iter().all(|e| e.upgrade().is_none()));
+    }
+
+    #[test]
+    fn clear_frees_everything() {
+        let live_nodes = Rc::new(Cell::new(0));
+        let live_edges = Rc::new(Cell::new(0));
+        let (mut g, _) = cyclic_graph(&live_nodes, &live_edges);
+
+        g.clear();
+        assert_eq!((live_nodes.get(), live_edges.get()), (0, 0));
+        assert_eq!((g.order(), g.size()), (0, 0));
+    }
+
+    #[test]
+    fn remove_node_frees_node_and_incident_edges() {
+        let live_nodes = Rc::new(Cell::new(0));
+        let live_edges = Rc::new(Cell::new(0));
+        let (mut g, nodes) = cyclic_graph(&live_nodes, &live_edges);
+        let weak_node = Rc::downgrade(&g.nodes[&nodes[2]]);
+
+        drop(g.remove_node(nodes[2]));
+        assert!(weak_node.upgrade().is_none());
+        assert_eq!((live_nodes.get(), live_edges.get()), (3, 3));
+
+        drop(g.remove_node(nodes[3]));
+        assert_eq!((live_nodes.get(), live_edges.get()), (2, 2));
     }
 }
@@ -0,0 +1,460 @@
+use graph::Graph;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use weight::{MinScored, Weight};
+
+/**
+ * Distances from a single source node together with the shortest-path tree
+ * that realizes them. Only nodes reachable from the source have a distance.
+ */
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<TIndex: Hash + Eq, W> {
+    source: TIndex,
+    dist: HashMap<TIndex, W>,
+
+    // Maps every reached node other than the source to its predecessor on a
+    // shortest path and to the edge leading from that predecessor.
+    pred: HashMap<TIndex, (TIndex, TIndex)>,
+}
+
+impl<TIndex: Copy + Hash + Eq, W: Copy> ShortestPaths<TIndex, W> {
+    fn new(source: TIndex) -> ShortestPaths<TIndex, W> {
+        ShortestPaths {
+            source,
+            dist: HashMap::new(),
+            pred: HashMap::new(),
+        }
+    }
+
+    pub fn source(&self) -> TIndex {
+        self.source
+    }
+
+    // Returns the length of a shortest path from the source to a node.
+    pub fn distance(&self, node: TIndex) -> Option<W> {
+        self.dist.get(&node).cloned()
+    }
+
+    // Returns the distance to every node reachable from the source.
+    pub fn distances(&self) -> &HashMap<TIndex, W> {
+        &self.dist
+    }
+
+    // Returns the node before a node on its shortest path from the source.
+    pub fn predecessor(&self, node: TIndex) -> Option<TIndex> {
+        self.pred.get(&node).map(|&(prev, _)| prev)
+    }
+
+    // Returns the last edge on a node's shortest path from the source.
+    pub fn predecessor_edge(&self, node: TIndex) -> Option<TIndex> {
+        self.pred.get(&node).map(|&(_, edge)| edge)
+    }
+
+    // Returns the nodes on a shortest path from the source to a node, both
+    // ends included.
+    pub fn path_to(&self, node: TIndex) -> Option<Vec<TIndex>> {
+        if !self.dist.contains_key(&node) {
+            return None;
+        }
+        let mut path = vec![node];
+        let mut cur = node;
+        while let Some(&(prev, _)) = self.pred.get(&cur) {
+            path.push(prev);
+            cur = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    // Returns the edges on a shortest path from the source to a node.
+    pub fn edge_path_to(&self, node: TIndex) -> Option<Vec<TIndex>> {
+        if !self.dist.contains_key(&node) {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut cur = node;
+        while let Some(&(prev, edge)) = self.pred.get(&cur) {
+            path.push(edge);
+            cur = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// Best-first search along outgoing edges shared by Dijkstra and A*. Nodes are
+// expanded in order of distance plus heuristic; a node is expanded again if a
+// shorter path to it turns up later, so inconsistent heuristics stay correct.
+// Stops as soon as the target (if any) is expanded.
+fn best_first<N, E, G, W, F, H>(
+    graph: &G,
+    source: G::TIndex,
+    target: Option<G::TIndex>,
+    mut weight: F,
+    mut heuristic: H,
+) -> ShortestPaths<G::TIndex, W>
+    where G: Graph<N, E>,
+          W: Weight,
+          F: FnMut(G::TIndex, &E) -> W,
+          H: FnMut(G::TIndex) -> W
+{
+    let mut paths = ShortestPaths::new(source);
+    if !graph.has_node(source) {
+        return paths;
+    }
+
+    let mut heap = BinaryHeap::new();
+    paths.dist.insert(source, W::zero());
+    heap.push(MinScored(heuristic(source), (W::zero(), source)));
+
+    while let Some(MinScored(_, (dist, node))) = heap.pop() {
+        if dist > paths.dist[&node] {
+            continue;
+        }
+        if Some(node) == target {
+            break;
+        }
+
+        for edge in graph.get_succ_edges_of(node) {
+            let (next, data) = match (graph.get_edge_end(edge), graph.get_edge_data(edge)) {
+                (Some(next), Some(data)) => (next, data),
+                _ => continue,
+            };
+            let next_dist = dist + weight(edge, data);
+            match paths.dist.entry(next) {
+                Entry::Occupied(ref entry) if *entry.get() <= next_dist => continue,
+                Entry::Occupied(mut entry) => {
+                    entry.insert(next_dist);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(next_dist);
+                }
+            }
+            paths.pred.insert(next, (node, edge));
+            heap.push(MinScored(next_dist + heuristic(next), (next_dist, next)));
+        }
+    }
+    paths
+}
+
+// Computes shortest paths from a source to every reachable node, following
+// edges in their direction. Edge weights must not be negative.
+pub fn dijkstra<N, E, G, W, F>(graph: &G, source: G::TIndex, weight: F)
+    -> ShortestPaths<G::TIndex, W>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut weight = weight;
+    best_first(graph, source, None, |_, data| weight(data), |_| W::zero())
+}
+
+// Computes a shortest path between two nodes, stopping as soon as the target
+// is settled. Returns its length and its nodes, or None if it is unreachable.
+pub fn dijkstra_to<N, E, G, W, F>(graph: &G, source: G::TIndex, target: G::TIndex, weight: F)
+    -> Option<(W, Vec<G::TIndex>)>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    astar(graph, source, target, weight, |_| W::zero())
+}
+
+// Computes a shortest path between two nodes, guided by a heuristic that
+// estimates the remaining distance from a node to the target. The result is
+// optimal as long as the heuristic never overestimates.
+pub fn astar<N, E, G, W, F, H>(graph: &G, source: G::TIndex, target: G::TIndex, weight: F, heuristic: H)
+    -> Option<(W, Vec<G::TIndex>)>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W, H: FnMut(G::TIndex) -> W
+{
+    let mut weight = weight;
+    let paths = best_first(graph, source, Some(target), |_, data| weight(data), heuristic);
+    let dist = paths.distance(target)?;
+    paths.path_to(target).map(|path| (dist, path))
+}
+
+/**
+ * Error returned when a negative cycle makes shortest paths undefined. The
+ * cycle is listed in edge direction, starting at an arbitrary node of it and
+ * without repeating that node at the end.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegativeCycle<TIndex> {
+    nodes: Vec<TIndex>,
+}
+
+impl<TIndex> NegativeCycle<TIndex> {
+    pub fn nodes(&self) -> &[TIndex] {
+        &self.nodes
+    }
+
+    pub fn into_nodes(self) -> Vec<TIndex> {
+        self.nodes
+    }
+}
+
+impl<TIndex: fmt::Debug> fmt::Display for NegativeCycle<TIndex> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "graph contains a negative cycle through {:?}", self.nodes)
+    }
+}
+
+impl<TIndex: fmt::Debug> error::Error for NegativeCycle<TIndex> {}
+
+// An edge copied out of the graph as (edge, start, end, weight).
+type WeightedEdge<T, W> = (T, T, T, W);
+
+// Shortest paths from every node, keyed by source.
+pub type AllPairs<TIndex, W> = HashMap<TIndex, ShortestPaths<TIndex, W>>;
+
+// Runs Bellman-Ford relaxation rounds over a snapshot of the edges until
+// nothing changes, starting from whatever distances are already in paths.
+// Every node can be relaxed at most order - 1 times without a negative cycle,
+// so an edge that still relaxes after that many rounds lies on or behind one.
+fn relax_until_stable<T, W>(order: usize, edges: &[WeightedEdge<T, W>], paths: &mut ShortestPaths<T, W>)
+    -> Result<(), NegativeCycle<T>>
+    where T: Copy + Hash + Eq, W: Weight
+{
+    for round in 0..order.max(1) {
+        let mut changed = None;
+        for &(edge, start, end, weight) in edges {
+            let next_dist = match paths.dist.get(&start) {
+                Some(&dist) => dist + weight,
+                None => continue,
+            };
+            if paths.dist.get(&end).is_none_or(|&old| next_dist < old) {
+                paths.dist.insert(end, next_dist);
+                paths.pred.insert(end, (start, edge));
+                changed = Some(end);
+            }
+        }
+        match changed {
+            None => return Ok(()),
+            Some(node) if round + 1 == order.max(1) => return Err(trace_cycle(order, node, paths)),
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+// Walks back from a node that relaxed in the last round. After order steps
+// the walk is guaranteed to be inside the cycle, which is then read off.
+fn trace_cycle<T, W>(order: usize, node: T, paths: &ShortestPaths<T, W>) -> NegativeCycle<T>
+    where T: Copy + Hash + Eq
+{
+    let mut cur = node;
+    for _ in 0..order {
+        cur = paths.pred[&cur].0;
+    }
+    let mut nodes = vec![cur];
+    let mut prev = paths.pred[&cur].0;
+    while prev != cur {
+        nodes.push(prev);
+        prev = paths.pred[&prev].0;
+    }
+    nodes.reverse();
+    NegativeCycle { nodes }
+}
+
+// Copies every edge out of the graph together with its endpoints and weight.
+fn weighted_edges<N, E, G, W, F>(graph: &G, mut weight: F) -> Vec<WeightedEdge<G::TIndex, W>>
+    where G: Graph<N, E>, F: FnMut(&E) -> W
+{
+    graph.edges()
+        .filter_map(|edge| {
+            let (start, end) = graph.get_nodes_of(edge)?;
+            let data = graph.get_edge_data(edge)?;
+            Some((edge, start, end, weight(data)))
+        })
+        .collect()
+}
+
+// Computes shortest paths from a source to every reachable node, allowing
+// negative edge weights. Fails with the offending cycle if a negative cycle
+// is reachable from the source. Runs in O(order * size).
+pub fn bellman_ford<N, E, G, W, F>(graph: &G, source: G::TIndex, weight: F)
+    -> Result<ShortestPaths<G::TIndex, W>, NegativeCycle<G::TIndex>>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let mut paths = ShortestPaths::new(source);
+    if !graph.has_node(source) {
+        return Ok(paths);
+    }
+    paths.dist.insert(source, W::zero());
+    let edges = weighted_edges(graph, weight);
+    relax_until_stable(graph.order(), &edges, &mut paths)?;
+    Ok(paths)
+}
+
+// Computes shortest paths between all pairs of nodes with Johnson's
+// algorithm: one Bellman-Ford pass finds node potentials that make every
+// edge weight non-negative, then Dijkstra runs from each node on the
+// reweighted graph. Suited to sparse graphs, where it beats Floyd-Warshall.
+// Fails with a cycle if the graph contains any negative cycle.
+pub fn johnson<N, E, G, W, F>(graph: &G, weight: F)
+    -> Result<AllPairs<G::TIndex, W>, NegativeCycle<G::TIndex>>
+    where G: Graph<N, E>, W: Weight, F: FnMut(&E) -> W
+{
+    let first = match graph.nodes().next() {
+        Some(node) => node,
+        None => return Ok(HashMap::new()),
+    };
+    let edges = weighted_edges(graph, weight);
+
+    // Starting every node at zero stands in for the usual extra node with a
+    // zero-weight edge to all others.
+    let mut potentials = ShortestPaths::new(first);
+    for node in graph.nodes() {
+        potentials.dist.insert(node, W::zero());
+    }
+    relax_until_stable(graph.order(), &edges, &mut potentials)?;
+    let h = potentials.dist;
+
+    let reduced: HashMap<G::TIndex, W> = edges.iter()
+        .map(|&(edge, start, end, weight)| (edge, weight + h[&start] - h[&end]))
+        .collect();
+
+    let mut all = HashMap::with_capacity(graph.order());
+    for source in graph.nodes() {
+        let mut paths = best_first(graph, source, None, |edge, _| reduced[&edge], |_| W::zero());
+        for (node, dist) in paths.dist.iter_mut() {
+            *dist = *dist + h[node] - h[&source];
+        }
+        all.insert(source, paths);
+    }
+    Ok(all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    //      7      9
+    //   0 --> 1 ----> 2
+    //   | \         ^ |
+    // 14|  \9    2 /  | 6
+    //   v   > 3 --    v
+    //   5 <--------- 4 <-- 3 (11)
+    //          9
+    fn weighted() -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b, w) in &[(0, 1, 7), (0, 3, 9), (0, 5, 14), (1, 2, 10), (1, 3, 15),
+                            (3, 2, 2), (3, 4, 11), (2, 4, 6), (4, 5, 9)] {
+            g.add_edge(n[a], n[b], w);
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn single_source_distances() {
+        let (g, n) = weighted();
+        let paths = dijkstra(&g, n[0], |&w| w);
+        let dists: Vec<_> = n.iter().map(|&v| paths.distance(v)).collect();
+        assert_eq!(dists, vec![Some(0), Some(7), Some(11), Some(9), Some(17), Some(14)]);
+        assert_eq!(paths.path_to(n[4]), Some(vec![n[0], n[3], n[2], n[4]]));
+        assert_eq!(paths.edge_path_to(n[4]).map(|p| p.len()), Some(3));
+        assert_eq!(paths.predecessor(n[2]), Some(n[3]));
+
+        let from_4 = dijkstra(&g, n[4], |&w| w);
+        assert_eq!(from_4.distance(n[0]), None);
+        assert_eq!(from_4.path_to(n[1]), None);
+    }
+
+    #[test]
+    fn point_to_point() {
+        let (g, n) = weighted();
+        assert_eq!(dijkstra_to(&g, n[0], n[2], |&w| w), Some((11, vec![n[0], n[3], n[2]])));
+        assert_eq!(dijkstra_to(&g, n[0], n[0], |&w| w), Some((0, vec![n[0]])));
+        assert_eq!(dijkstra_to(&g, n[5], n[0], |&w| w), None);
+    }
+
+    #[test]
+    fn astar_on_grid() {
+        // 10x10 grid with unit edges in both directions and a wall down the
+        // middle that only has a gap in the bottom row.
+        let mut g = AdjListGraph::new();
+        let mut ids = HashMap::new();
+        for x in 0..10i32 {
+            for y in 0..10i32 {
+                ids.insert(g.add_node((x, y)), (x, y));
+            }
+        }
+        let at: HashMap<(i32, i32), i64> = ids.iter().map(|(&i, &p)| (p, i)).collect();
+        for x in 0..10i32 {
+            for y in 0..10i32 {
+                for &(dx, dy) in &[(1, 0), (0, 1)] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    let wall = (x == 4 && nx == 5) && y != 9;
+                    if nx < 10 && ny < 10 && !wall {
+                        g.add_edge(at[&(x, y)], at[&(nx, ny)], 1);
+                        g.add_edge(at[&(nx, ny)], at[&(x, y)], 1);
+                    }
+                }
+            }
+        }
+
+        let goal = (9, 0);
+        let manhattan = |n: i64| {
+            let (x, y) = *g.get_node_data(n).unwrap();
+            (goal.0 - x).abs() + (goal.1 - y).abs()
+        };
+        let (dist, path) = astar(&g, at[&(0, 0)], at[&goal], |&w| w, manhattan).unwrap();
+        assert_eq!(dist, 9 + 2 * 9);
+        assert_eq!(path.len() as i32, dist + 1);
+        assert!(path.contains(&at[&(4, 9)]));
+    }
+
+    // Same shape as the graph above, but 1 -> 3 costs -4 and 4 -> 2 costs -2.
+    fn with_negative_edges() -> (AdjListGraph<(), i32>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..6).map(|_| g.add_node(())).collect();
+        for &(a, b, w) in &[(0, 1, 7), (0, 3, 9), (0, 5, 14), (1, 2, 10), (1, 3, -4),
+                            (3, 2, 2), (3, 4, 1), (4, 2, -2), (4, 5, 9)] {
+            g.add_edge(n[a], n[b], w);
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn bellman_ford_with_negative_edges() {
+        let (g, n) = with_negative_edges();
+        let paths = bellman_ford(&g, n[0], |&w| w).unwrap();
+        let dists: Vec<_> = n.iter().map(|&v| paths.distance(v)).collect();
+        assert_eq!(dists, vec![Some(0), Some(7), Some(2), Some(3), Some(4), Some(13)]);
+        assert_eq!(paths.path_to(n[2]), Some(vec![n[0], n[1], n[3], n[4], n[2]]));
+
+        // Non-negative weights agree with Dijkstra.
+        let (g, n) = weighted();
+        let expected = dijkstra(&g, n[0], |&w| w);
+        assert_eq!(bellman_ford(&g, n[0], |&w| w).unwrap().distances(), expected.distances());
+    }
+
+    #[test]
+    fn negative_cycle_is_reported() {
+        let (mut g, n) = with_negative_edges();
+        g.add_edge(n[2], n[1], -9);
+        let cycle = bellman_ford(&g, n[0], |&w| w).unwrap_err().into_nodes();
+        let total: i32 = cycle.iter()
+            .zip(cycle.iter().cycle().skip(1))
+            .map(|(&a, &b)| *g.get_edge_data(g.get_edge(a, b).unwrap()).unwrap())
+            .sum();
+        assert!(total < 0);
+        assert!(johnson(&g, |&w| w).is_err());
+
+        // A cycle the source cannot reach does not matter to Bellman-Ford.
+        assert!(bellman_ford(&g, n[5], |&w| w).is_ok());
+    }
+
+    #[test]
+    fn johnson_matches_bellman_ford() {
+        let (g, n) = with_negative_edges();
+        let all = johnson(&g, |&w| w).unwrap();
+        assert_eq!(all.len(), n.len());
+        for &source in &n {
+            let expected = bellman_ford(&g, source, |&w| w).unwrap();
+            assert_eq!(all[&source].distances(), expected.distances());
+        }
+        assert_eq!(all[&n[1]].path_to(n[2]), Some(vec![n[1], n[3], n[4], n[2]]));
+    }
+}
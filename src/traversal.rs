@@ -0,0 +1,439 @@
+use graph::Graph;
+use std::collections::{HashSet, VecDeque};
+use std::marker::PhantomData;
+
+// Which edges to follow when moving from a node to its neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    // Follow edges from start to end, i.e. visit successors
+    Outgoing,
+
+    // Follow edges from end to start, i.e. visit predecessors
+    Incoming,
+
+    // Ignore edge direction
+    Both,
+}
+
+impl Direction {
+    // Returns the direction that walks the same edges the other way round.
+    pub fn reversed(self) -> Direction {
+        match self {
+            Direction::Outgoing => Direction::Incoming,
+            Direction::Incoming => Direction::Outgoing,
+            Direction::Both => Direction::Both,
+        }
+    }
+}
+
+// Returns an iterator over the edges incident on a node in the given
+// direction, paired with the node at their other end. With Direction::Both a
+// self-loop is reported twice, once as outgoing and once as incoming.
+pub fn incident_edges<'a, N, E, G>(graph: &'a G, node: G::TIndex, direction: Direction)
+    -> Box<dyn Iterator<Item = (G::TIndex, G::TIndex)> + 'a>
+    where G: Graph<N, E>
+{
+    let outgoing = move || graph.get_succ_edges_of(node)
+        .filter_map(move |e| graph.get_edge_end(e).map(|n| (e, n)));
+    let incoming = move || graph.get_pred_edges_of(node)
+        .filter_map(move |e| graph.get_edge_start(e).map(|n| (e, n)));
+
+    match direction {
+        Direction::Outgoing => Box::new(outgoing()),
+        Direction::Incoming => Box::new(incoming()),
+        Direction::Both => Box::new(outgoing().chain(incoming())),
+    }
+}
+
+// Returns an iterator over the neighbors of a node in the given direction.
+// A neighbor appears once per edge connecting it to the node.
+pub fn neighbors<'a, N, E, G>(graph: &'a G, node: G::TIndex, direction: Direction)
+    -> Box<dyn Iterator<Item = G::TIndex> + 'a>
+    where G: Graph<N, E>
+{
+    match direction {
+        Direction::Outgoing => graph.get_succ_nodes_of(node),
+        Direction::Incoming => graph.get_pred_nodes_of(node),
+        Direction::Both => Box::new(graph.get_succ_nodes_of(node).chain(graph.get_pred_nodes_of(node))),
+    }
+}
+
+// ============================================================================
+// EVENTS AND VISITORS
+// ============================================================================
+
+// Events produced while traversing; each carries the node and its depth,
+// i.e. its distance in edges from the node the traversal started at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<TIndex> {
+    // The node has been reached for the first time
+    Discover(TIndex, usize),
+
+    // All of the node's neighbors have been examined
+    Finish(TIndex, usize),
+}
+
+// Callbacks invoked by Bfs::visit and Dfs::visit. Both methods do nothing by
+// default, so implementors only override the events they care about.
+pub trait Visitor<TIndex> {
+    fn discover(&mut self, _node: TIndex, _depth: usize) {}
+
+    fn finish(&mut self, _node: TIndex, _depth: usize) {}
+}
+
+// ============================================================================
+// BREADTH-FIRST SEARCH
+// ============================================================================
+
+/**
+ * A lazy breadth-first traversal, iterating over (node, depth) pairs in the
+ * order the nodes are discovered.
+ *
+ * The visited set survives start_from(), so calling it again with a node that
+ * has not been reached yet continues the traversal into another part of the
+ * graph. restart() clears the traversal but keeps its buffers allocated, so
+ * one Bfs can be reused across many runs without reallocating.
+ */
+pub struct Bfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    graph: &'a G,
+    direction: Direction,
+    max_depth: Option<usize>,
+
+    queue: VecDeque<(G::TIndex, usize)>,
+    events: VecDeque<Event<G::TIndex>>,
+    visited: HashSet<G::TIndex>,
+
+    marker: PhantomData<fn() -> (N, E)>,
+}
+
+impl<'a, N, E, G> Bfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    pub fn new(graph: &'a G, start: G::TIndex, direction: Direction) -> Bfs<'a, N, E, G> {
+        let mut bfs = Bfs {
+            graph,
+            direction,
+            max_depth: None,
+            queue: VecDeque::new(),
+            events: VecDeque::new(),
+            visited: HashSet::new(),
+            marker: PhantomData,
+        };
+        bfs.start_from(start);
+        bfs
+    }
+
+    // Stops the traversal from expanding nodes at the given depth, so that
+    // nothing further than max_depth edges away is discovered.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    // Continues the traversal from another node, unless it has already been
+    // visited. Nodes reached from it are reported at their depth from it.
+    pub fn start_from(&mut self, start: G::TIndex) {
+        if self.graph.has_node(start) && self.visited.insert(start) {
+            self.queue.push_back((start, 0));
+            self.events.push_back(Event::Discover(start, 0));
+        }
+    }
+
+    // Forgets everything visited so far and starts over from a new node.
+    pub fn restart(&mut self, start: G::TIndex) {
+        self.queue.clear();
+        self.events.clear();
+        self.visited.clear();
+        self.start_from(start);
+    }
+
+    pub fn is_visited(&self, node: G::TIndex) -> bool {
+        self.visited.contains(&node)
+    }
+
+    // Returns the next discover or finish event, or None once every node
+    // reachable from the start nodes has been finished.
+    pub fn next_event(&mut self) -> Option<Event<G::TIndex>> {
+        if self.events.is_empty() {
+            let (node, depth) = self.queue.pop_front()?;
+            if self.max_depth.is_none_or(|max| depth < max) {
+                for next in neighbors(self.graph, node, self.direction) {
+                    if self.visited.insert(next) {
+                        self.queue.push_back((next, depth + 1));
+                        self.events.push_back(Event::Discover(next, depth + 1));
+                    }
+                }
+            }
+            self.events.push_back(Event::Finish(node, depth));
+        }
+        self.events.pop_front()
+    }
+
+    // Runs the traversal to completion, reporting every event to a visitor.
+    pub fn visit<V: Visitor<G::TIndex>>(&mut self, visitor: &mut V) {
+        while let Some(event) = self.next_event() {
+            match event {
+                Event::Discover(node, depth) => visitor.discover(node, depth),
+                Event::Finish(node, depth) => visitor.finish(node, depth),
+            }
+        }
+    }
+}
+
+impl<'a, N, E, G> Iterator for Bfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    type Item = (G::TIndex, usize);
+
+    fn next(&mut self) -> Option<(G::TIndex, usize)> {
+        while let Some(event) = self.next_event() {
+            if let Event::Discover(node, depth) = event {
+                return Some((node, depth));
+            }
+        }
+        None
+    }
+}
+
+// ============================================================================
+// DEPTH-FIRST SEARCH
+// ============================================================================
+
+// A node on the DFS stack, its depth and the neighbors still to be examined.
+type Frame<'a, TIndex> = (TIndex, usize, Box<dyn Iterator<Item = TIndex> + 'a>);
+
+/**
+ * A lazy depth-first traversal, iterating over (node, depth) pairs in
+ * preorder. The traversal keeps an explicit stack of neighbor iterators
+ * instead of recursing, so it handles arbitrarily deep graphs.
+ *
+ * start_from() and restart() behave as they do for Bfs.
+ */
+pub struct Dfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    graph: &'a G,
+    direction: Direction,
+    max_depth: Option<usize>,
+
+    stack: Vec<Frame<'a, G::TIndex>>,
+    events: VecDeque<Event<G::TIndex>>,
+    visited: HashSet<G::TIndex>,
+
+    marker: PhantomData<fn() -> (N, E)>,
+}
+
+impl<'a, N, E, G> Dfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    pub fn new(graph: &'a G, start: G::TIndex, direction: Direction) -> Dfs<'a, N, E, G> {
+        let mut dfs = Dfs {
+            graph,
+            direction,
+            max_depth: None,
+            stack: Vec::new(),
+            events: VecDeque::new(),
+            visited: HashSet::new(),
+            marker: PhantomData,
+        };
+        dfs.start_from(start);
+        dfs
+    }
+
+    // Stops the traversal from expanding nodes at the given depth, so that
+    // nothing further than max_depth edges away is discovered.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    // Continues the traversal from another node, unless it has already been
+    // visited. Nodes started from in a row are all reported as discovered,
+    // and the one given last is explored first.
+    pub fn start_from(&mut self, start: G::TIndex) {
+        if self.graph.has_node(start) && self.visited.insert(start) {
+            self.push(start, 0);
+            self.events.push_back(Event::Discover(start, 0));
+        }
+    }
+
+    // Forgets everything visited so far and starts over from a new node.
+    pub fn restart(&mut self, start: G::TIndex) {
+        self.stack.clear();
+        self.events.clear();
+        self.visited.clear();
+        self.start_from(start);
+    }
+
+    pub fn is_visited(&self, node: G::TIndex) -> bool {
+        self.visited.contains(&node)
+    }
+
+    fn push(&mut self, node: G::TIndex, depth: usize) {
+        let next: Box<dyn Iterator<Item = G::TIndex> + 'a> =
+            if self.max_depth.is_none_or(|max| depth < max) {
+                neighbors(self.graph, node, self.direction)
+            } else {
+                Box::new(::std::iter::empty())
+            };
+        self.stack.push((node, depth, next));
+    }
+
+    // Returns the next discover or finish event, or None once every node
+    // reachable from the start nodes has been finished.
+    pub fn next_event(&mut self) -> Option<Event<G::TIndex>> {
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+
+        loop {
+            let (node, depth, next) = match self.stack.last_mut() {
+                Some(&mut (node, depth, ref mut neighbors)) => (node, depth, neighbors.next()),
+                None => return None,
+            };
+            match next {
+                Some(next) => {
+                    if self.visited.insert(next) {
+                        self.push(next, depth + 1);
+                        return Some(Event::Discover(next, depth + 1));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    return Some(Event::Finish(node, depth));
+                }
+            }
+        }
+    }
+
+    // Runs the traversal to completion, reporting every event to a visitor.
+    pub fn visit<V: Visitor<G::TIndex>>(&mut self, visitor: &mut V) {
+        while let Some(event) = self.next_event() {
+            match event {
+                Event::Discover(node, depth) => visitor.discover(node, depth),
+                Event::Finish(node, depth) => visitor.finish(node, depth),
+            }
+        }
+    }
+}
+
+impl<'a, N, E, G> Iterator for Dfs<'a, N, E, G>
+    where G: 'a + Graph<N, E>
+{
+    type Item = (G::TIndex, usize);
+
+    fn next(&mut self) -> Option<(G::TIndex, usize)> {
+        while let Some(event) = self.next_event() {
+            if let Event::Discover(node, depth) = event {
+                return Some((node, depth));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adjlistgraph::AdjListGraph;
+
+    // 0 -> 1 -> 3 -> 4
+    //  \-> 2 --^
+    fn diamond() -> (AdjListGraph<(), ()>, Vec<i64>) {
+        let mut g = AdjListGraph::new();
+        let n: Vec<i64> = (0..5).map(|_| g.add_node(())).collect();
+        for &(a, b) in &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)] {
+            g.add_edge(n[a], n[b], ());
+        }
+        (g, n)
+    }
+
+    #[test]
+    fn bfs_depths_and_directions() {
+        let (g, n) = diamond();
+        let out: Vec<_> = Bfs::new(&g, n[0], Direction::Outgoing).collect();
+        assert_eq!(out[0], (n[0], 0));
+        assert_eq!(out[3], (n[3], 2));
+        assert_eq!(out[4], (n[4], 3));
+
+        let incoming: Vec<_> = Bfs::new(&g, n[3], Direction::Incoming).map(|(v, _)| v).collect();
+        assert_eq!(incoming.len(), 4);
+        assert!(!incoming.contains(&n[4]));
+
+        let mut limited = Bfs::new(&g, n[2], Direction::Both);
+        limited.set_max_depth(Some(1));
+        assert_eq!(limited.count(), 3);
+    }
+
+    #[test]
+    fn dfs_reuses_visited_set() {
+        let (g, n) = diamond();
+        let mut dfs = Dfs::new(&g, n[3], Direction::Outgoing);
+        assert_eq!(dfs.by_ref().map(|(v, _)| v).collect::<Vec<_>>(), vec![n[3], n[4]]);
+
+        // The second run skips what the first one already reached.
+        dfs.start_from(n[0]);
+        assert_eq!(dfs.by_ref().count(), 3);
+
+        dfs.restart(n[3]);
+        assert_eq!(dfs.count(), 2);
+    }
+
+    struct Recorder(Vec<Event<i64>>);
+
+    impl Visitor<i64> for Recorder {
+        fn discover(&mut self, node: i64, depth: usize) {
+            self.0.push(Event::Discover(node, depth));
+        }
+
+        fn finish(&mut self, node: i64, depth: usize) {
+            self.0.push(Event::Finish(node, depth));
+        }
+    }
+
+    #[test]
+    fn dfs_finishes_descendants_first() {
+        let (g, n) = diamond();
+        let mut recorder = Recorder(Vec::new());
+        Dfs::new(&g, n[1], Direction::Outgoing).visit(&mut recorder);
+        assert_eq!(recorder.0, vec![
+            Event::Discover(n[1], 0),
+            Event::Discover(n[3], 1),
+            Event::Discover(n[4], 2),
+            Event::Finish(n[4], 2),
+            Event::Finish(n[3], 1),
+            Event::Finish(n[1], 0),
+        ]);
+    }
+
+    #[test]
+    fn dfs_keeps_every_start() {
+        let (g, n) = diamond();
+        let mut dfs = Dfs::new(&g, n[4], Direction::Outgoing);
+        dfs.start_from(n[2]);
+        let mut recorder = Recorder(Vec::new());
+        dfs.visit(&mut recorder);
+        assert_eq!(recorder.0, vec![
+            Event::Discover(n[4], 0),
+            Event::Discover(n[2], 0),
+            Event::Discover(n[3], 1),
+            Event::Finish(n[3], 1),
+            Event::Finish(n[2], 0),
+            Event::Finish(n[4], 0),
+        ]);
+    }
+
+    #[test]
+    fn deep_path_does_not_overflow() {
+        let mut g = AdjListGraph::new();
+        let mut prev = g.add_node(());
+        let first = prev;
+        for _ in 0..100000 {
+            let next = g.add_node(());
+            g.add_edge(prev, next, ());
+            prev = next;
+        }
+        let last = Dfs::new(&g, first, Direction::Outgoing).last();
+        assert_eq!(last, Some((prev, 100000)));
+    }
+}
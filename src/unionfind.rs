@@ -0,0 +1,119 @@
+/**
+ * Disjoint sets over the elements 0..len(), with union by rank and path
+ * compression. Any sequence of m operations on n elements runs in
+ * O(m α(n)) time, which is effectively linear.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    sets: usize,
+}
+
+impl UnionFind {
+    // Creates n singleton sets.
+    pub fn new(n: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            sets: n,
+        }
+    }
+
+    // Adds a new singleton set and returns its element.
+    pub fn make_set(&mut self) -> usize {
+        let x = self.parent.len();
+        self.parent.push(x);
+        self.rank.push(0);
+        self.sets += 1;
+        x
+    }
+
+    // Number of elements across all sets
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    // Number of disjoint sets
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    // Returns the representative of the set containing x, pointing every
+    // element on the way straight at it. Panics if x is out of range.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut cur = x;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    // Merges the sets containing a and b. Returns false if they already were
+    // the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.rank[a] < self.rank[b] {
+            self.parent[a] = b;
+        } else {
+            self.parent[b] = a;
+            if self.rank[a] == self.rank[b] {
+                self.rank[a] += 1;
+            }
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_and_find() {
+        let mut sets = UnionFind::new(6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert!(sets.same_set(0, 3));
+        assert!(!sets.same_set(0, 4));
+        assert_eq!(sets.set_count(), 3);
+
+        let x = sets.make_set();
+        assert_eq!(x, 6);
+        assert!(sets.union(x, 5));
+        assert_eq!(sets.find(x), sets.find(5));
+        assert_eq!(sets.set_count(), 3);
+    }
+
+    #[test]
+    fn long_chain_is_compressed() {
+        let n = 100_000;
+        let mut sets = UnionFind::new(n);
+        for i in 1..n {
+            sets.union(i - 1, i);
+        }
+        let root = sets.find(n - 1);
+        assert!((0..n).all(|i| sets.parent[i] == root || sets.find(i) == root));
+        assert_eq!(sets.set_count(), 1);
+    }
+}
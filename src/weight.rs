@@ -0,0 +1,46 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Sub};
+
+// Numeric types that can serve as edge weights, path lengths or capacities.
+// Algorithms take a closure that extracts a Weight from each edge's data.
+pub trait Weight: Copy + Ord + Add<Output = Self> + Sub<Output = Self> {
+    fn zero() -> Self;
+}
+
+macro_rules! impl_weight {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> $t {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_weight!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// Pairs an item with a score, ordered so that a BinaryHeap pops the item with
+// the smallest score first.
+pub(crate) struct MinScored<W, T>(pub W, pub T);
+
+impl<W: Ord, T> PartialEq for MinScored<W, T> {
+    fn eq(&self, other: &MinScored<W, T>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<W: Ord, T> Eq for MinScored<W, T> {}
+
+impl<W: Ord, T> PartialOrd for MinScored<W, T> {
+    fn partial_cmp(&self, other: &MinScored<W, T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: Ord, T> Ord for MinScored<W, T> {
+    fn cmp(&self, other: &MinScored<W, T>) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}